@@ -1,4 +1,5 @@
 import { env } from "@/env";
+import { logout, store, tokenRefreshed } from "@/store";
 import { HttpStatusCode } from "@/utils";
 import type { JSONContent } from "@tiptap/react";
 import { z } from "zod";
@@ -66,6 +67,7 @@ async function signup(body: z.infer<typeof signupInput>): Promise<Result<null, s
 
 export type LoginOutput = {
   token: string;
+  refreshToken: string;
   user: {
     id: number;
     email: string;
@@ -108,6 +110,78 @@ async function login(body: z.infer<typeof loginInput>): Promise<Result<LoginOutp
   }
 }
 
+export type RefreshOutput = {
+  token: string;
+  refreshToken: string;
+};
+
+// refresh tokens are rotated on every use, so requests failing at the same time share one refresh
+let pendingRefresh: Promise<string | null> | null = null;
+
+async function refreshAccessToken(): Promise<string | null> {
+  // e.g. logged in before refresh tokens were stored
+  const { refreshToken } = store.getState().app;
+  if (!refreshToken) {
+    store.dispatch(logout());
+    return null;
+  }
+
+  try {
+    const resp = await fetch(`${baseUrl}/api/auth/refresh`, {
+      method: "POST",
+      body: JSON.stringify({ refreshToken }),
+      headers: {
+        "Content-Type": "application/json",
+      },
+    });
+
+    // the session expired or was revoked
+    if (resp.status === HttpStatusCode.UNAUTHORIZED) {
+      store.dispatch(logout());
+      return null;
+    }
+
+    if (!resp.ok) {
+      console.error(resp.statusText);
+      return null;
+    }
+
+    const output = (await resp.json()) as RefreshOutput;
+    store.dispatch(tokenRefreshed(output));
+    return output.token;
+  } catch (err) {
+    console.error(err);
+    return null;
+  }
+}
+
+type AuthRequestInit = Omit<RequestInit, "headers"> & { headers?: Record<string, string> };
+
+/** access tokens are short lived, so on 401 the tokens are refreshed and the request is retried once */
+async function authFetch(token: string, url: string, init: AuthRequestInit = {}): Promise<Response> {
+  const request = (accessToken: string) =>
+    fetch(url, { ...init, headers: { ...init.headers, Authorization: `bearer ${accessToken}` } });
+
+  const resp = await request(token);
+  if (resp.status !== HttpStatusCode.UNAUTHORIZED) {
+    return resp;
+  }
+
+  // another request may have refreshed the tokens in the meantime
+  const current = store.getState().app.token;
+  if (current && current !== token) {
+    return request(current);
+  }
+
+  if (!pendingRefresh) {
+    pendingRefresh = refreshAccessToken().finally(() => {
+      pendingRefresh = null;
+    });
+  }
+  const refreshed = await pendingRefresh;
+  return refreshed ? request(refreshed) : resp;
+}
+
 const jsonContentSchema: z.ZodType<JSONContent> = z.lazy(
   () =>
     z
@@ -143,12 +217,11 @@ async function putEntry(token: string, body: z.infer<typeof entryInput>): Promis
   const { date, text } = body;
 
   try {
-    const resp = await fetch(`${baseUrl}/api/entry/${date}`, {
+    const resp = await authFetch(token, `${baseUrl}/api/entry/${date}`, {
       method: "PUT",
       body: JSON.stringify({ text }),
       headers: {
         "Content-Type": "application/json",
-        Authorization: `bearer ${token}`,
       },
     });
 
@@ -183,11 +256,8 @@ async function getEntry(
   }
 
   try {
-    const resp = await fetch(`${baseUrl}/api/entry/${body}`, {
+    const resp = await authFetch(token, `${baseUrl}/api/entry/${body}`, {
       method: "GET",
-      headers: {
-        Authorization: `bearer ${token}`,
-      },
     });
 
     // we don't care if entry for today's date exists or not
@@ -218,11 +288,8 @@ async function deleteEntry(token: string, body: z.infer<typeof deleteEntryInput>
   }
 
   try {
-    const resp = await fetch(`${baseUrl}/api/entry/${body}`, {
+    const resp = await authFetch(token, `${baseUrl}/api/entry/${body}`, {
       method: "DELETE",
-      headers: {
-        Authorization: `bearer ${token}`,
-      },
     });
 
     if (!resp.ok) {
@@ -241,11 +308,8 @@ export type GetAllEntryDatesOutput = Set<string>;
 
 async function getAllEntryDates(token: string): Promise<Result<GetAllEntryDatesOutput, string>> {
   try {
-    const resp = await fetch(`${baseUrl}/api/entry/dates`, {
+    const resp = await authFetch(token, `${baseUrl}/api/entry/dates`, {
       method: "GET",
-      headers: {
-        Authorization: `bearer ${token}`,
-      },
     });
 
     const output = new Set<string>(await resp.json());
@@ -1,4 +1,4 @@
-import { LoginOutput } from "@/api";
+import type { LoginOutput, RefreshOutput } from "@/api";
 import type { PayloadAction } from "@reduxjs/toolkit";
 import { configureStore, createSlice } from "@reduxjs/toolkit";
 
@@ -11,11 +11,13 @@ export interface User {
 
 export interface AppState {
   token: string | null;
+  refreshToken: string | null;
   user: User | null;
 }
 
 const initialState: AppState = {
   token: null,
+  refreshToken: null,
   user: null,
 };
 
@@ -26,27 +28,39 @@ export const appSlice = createSlice({
     loginSuccess(state, action: PayloadAction<LoginOutput>) {
       state.token = action.payload.token;
       localStorage.setItem("token", action.payload.token);
+      state.refreshToken = action.payload.refreshToken;
+      localStorage.setItem("refreshToken", action.payload.refreshToken);
       state.user = action.payload.user;
       localStorage.setItem("user", JSON.stringify(action.payload.user));
     },
+    tokenRefreshed(state, action: PayloadAction<RefreshOutput>) {
+      state.token = action.payload.token;
+      localStorage.setItem("token", action.payload.token);
+      state.refreshToken = action.payload.refreshToken;
+      localStorage.setItem("refreshToken", action.payload.refreshToken);
+    },
     logout(state) {
       state.token = null;
       localStorage.removeItem("token");
+      state.refreshToken = null;
+      localStorage.removeItem("refreshToken");
       state.user = null;
       localStorage.removeItem("user");
     },
     rehydrate(state) {
       // only runs on client
       const token = localStorage.getItem("token");
+      const refreshToken = localStorage.getItem("refreshToken");
       const user = localStorage.getItem("user");
       if (token) state.token = token;
+      if (refreshToken) state.refreshToken = refreshToken;
       if (user) state.user = JSON.parse(user);
     },
   },
 });
 
 // Action creators are generated for each case reducer function
-export const { loginSuccess, logout, rehydrate } = appSlice.actions;
+export const { loginSuccess, tokenRefreshed, logout, rehydrate } = appSlice.actions;
 
 export const store = configureStore({
   reducer: {
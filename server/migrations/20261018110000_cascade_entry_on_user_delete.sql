@@ -0,0 +1,19 @@
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "new_Entry" (
+    "user_id" INTEGER NOT NULL,
+    "date" DATETIME NOT NULL,
+    "text" JSONB NOT NULL,
+    "word_count" INTEGER NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY ("user_id", "date"),
+    CONSTRAINT "Entry_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "User" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_Entry" ("created_at", "date", "text", "user_id", "word_count") SELECT "created_at", "date", "text", "user_id", "word_count" FROM "Entry";
+DROP TABLE "Entry";
+ALTER TABLE "new_Entry" RENAME TO "Entry";
+CREATE UNIQUE INDEX "Entry_user_id_date_key" ON "Entry"("user_id", "date");
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
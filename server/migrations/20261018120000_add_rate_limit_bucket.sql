@@ -0,0 +1,6 @@
+-- CreateTable
+CREATE TABLE "rate_limit_bucket" (
+    "key" TEXT NOT NULL PRIMARY KEY,
+    "tokens" REAL NOT NULL,
+    "updated_at" REAL NOT NULL
+);
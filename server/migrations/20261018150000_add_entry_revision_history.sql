@@ -0,0 +1,15 @@
+-- CreateTable
+CREATE TABLE "entry_revision" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "user_id" INTEGER NOT NULL,
+    "date" DATETIME NOT NULL,
+    "text" JSONB NOT NULL,
+    "word_count" INTEGER NOT NULL,
+    "revision" INTEGER NOT NULL,
+    "saved_at" DATETIME NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT "entry_revision_user_id_date_fkey" FOREIGN KEY ("user_id", "date") REFERENCES "Entry" ("user_id", "date") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE INDEX "entry_revision_user_id_date_idx" ON "entry_revision"("user_id", "date");
@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "Entry" ADD COLUMN "deleted_at" DATETIME;
@@ -0,0 +1,33 @@
+-- CreateTable
+CREATE TABLE "entry_search" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "user_id" INTEGER NOT NULL,
+    "date" DATETIME NOT NULL,
+    "text" TEXT NOT NULL,
+    CONSTRAINT "entry_search_user_id_date_fkey" FOREIGN KEY ("user_id", "date") REFERENCES "Entry" ("user_id", "date") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "entry_search_user_id_date_key" ON "entry_search"("user_id", "date");
+
+-- not generated by prisma, which doesn't support virtual tables
+-- ref: https://www.sqlite.org/fts5.html#external_content_tables
+CREATE VIRTUAL TABLE "entry_fts" USING fts5(
+    "text",
+    content = 'entry_search',
+    content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER "entry_search_ai" AFTER INSERT ON "entry_search" BEGIN
+    INSERT INTO "entry_fts" ("rowid", "text") VALUES (new."id", new."text");
+END;
+
+CREATE TRIGGER "entry_search_ad" AFTER DELETE ON "entry_search" BEGIN
+    INSERT INTO "entry_fts" ("entry_fts", "rowid", "text") VALUES ('delete', old."id", old."text");
+END;
+
+CREATE TRIGGER "entry_search_au" AFTER UPDATE ON "entry_search" BEGIN
+    INSERT INTO "entry_fts" ("entry_fts", "rowid", "text") VALUES ('delete', old."id", old."text");
+    INSERT INTO "entry_fts" ("rowid", "text") VALUES (new."id", new."text");
+END;
@@ -0,0 +1,2 @@
+-- the plain text extraction changed, the server indexes all entries again on startup
+DELETE FROM "entry_search";
@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "User" ADD COLUMN "daily_word_goal" INTEGER NOT NULL DEFAULT 750;
@@ -0,0 +1,113 @@
+-- entry dates were stored as date times, e.g. `2026-10-18T00:00:00Z`, or as
+-- unix milliseconds by prisma. the day as written is kept, so that a date
+-- with an offset stays on the day the user picked. entries which end up on the
+-- same day are merged into the most recently updated one, preferring the ones
+-- not in the trash, and the others are kept as its revisions. the revision of
+-- a merged entry is bumped, so that no `If-Match` of the others matches it.
+
+-- RedefineTables
+PRAGMA defer_foreign_keys=ON;
+PRAGMA foreign_keys=OFF;
+CREATE TABLE "_entry_date" (
+    "user_id" INTEGER NOT NULL,
+    "old_date" NOT NULL,
+    "date" TEXT NOT NULL,
+    "rank" INTEGER NOT NULL,
+    "revision" INTEGER NOT NULL
+);
+INSERT INTO "_entry_date" ("user_id", "old_date", "date", "rank", "revision")
+SELECT
+    "user_id",
+    "date",
+    "new_date",
+    ROW_NUMBER() OVER ("day" ORDER BY "deleted_at" IS NOT NULL, "updated_at" DESC, "date" DESC),
+    CASE
+        WHEN COUNT(*) OVER "day" > 1 THEN MAX("revision") OVER "day" + 1
+        ELSE "revision"
+    END
+FROM (
+    SELECT
+        *,
+        CASE
+            WHEN typeof("date") = 'integer' THEN DATE("date" / 1000, 'unixepoch')
+            ELSE SUBSTR("date", 1, 10)
+        END AS "new_date"
+    FROM "Entry"
+)
+WINDOW "day" AS (PARTITION BY "user_id", "new_date");
+
+CREATE TABLE "new_Entry" (
+    "user_id" INTEGER NOT NULL,
+    "date" TEXT NOT NULL,
+    "text" JSONB NOT NULL,
+    "word_count" INTEGER NOT NULL,
+    "revision" INTEGER NOT NULL DEFAULT 1,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "deleted_at" DATETIME,
+
+    PRIMARY KEY ("user_id", "date"),
+    CONSTRAINT "Entry_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "User" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_Entry" ("user_id", "date", "text", "word_count", "revision", "created_at", "updated_at", "deleted_at")
+SELECT "Entry"."user_id", "_entry_date"."date", "text", "word_count", "_entry_date"."revision", "created_at", "updated_at", "deleted_at"
+FROM "Entry"
+JOIN "_entry_date" ON "_entry_date"."user_id" = "Entry"."user_id" AND "_entry_date"."old_date" = "Entry"."date"
+WHERE "_entry_date"."rank" = 1;
+
+CREATE TABLE "new_entry_revision" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "user_id" INTEGER NOT NULL,
+    "date" TEXT NOT NULL,
+    "text" JSONB NOT NULL,
+    "word_count" INTEGER NOT NULL,
+    "revision" INTEGER NOT NULL,
+    "saved_at" DATETIME NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT "entry_revision_user_id_date_fkey" FOREIGN KEY ("user_id", "date") REFERENCES "Entry" ("user_id", "date") ON DELETE CASCADE ON UPDATE CASCADE
+);
+INSERT INTO "new_entry_revision" ("id", "user_id", "date", "text", "word_count", "revision", "saved_at", "created_at")
+SELECT "id", "entry_revision"."user_id", "_entry_date"."date", "text", "word_count", "entry_revision"."revision", "saved_at", "created_at"
+FROM "entry_revision"
+JOIN "_entry_date" ON "_entry_date"."user_id" = "entry_revision"."user_id" AND "_entry_date"."old_date" = "entry_revision"."date";
+INSERT INTO "new_entry_revision" ("user_id", "date", "text", "word_count", "revision", "saved_at")
+SELECT "Entry"."user_id", "_entry_date"."date", "text", "word_count", "Entry"."revision", "updated_at"
+FROM "Entry"
+JOIN "_entry_date" ON "_entry_date"."user_id" = "Entry"."user_id" AND "_entry_date"."old_date" = "Entry"."date"
+WHERE "_entry_date"."rank" > 1;
+
+DROP TABLE "entry_revision";
+ALTER TABLE "new_entry_revision" RENAME TO "entry_revision";
+CREATE INDEX "entry_revision_user_id_date_idx" ON "entry_revision"("user_id", "date");
+DROP TABLE "Entry";
+ALTER TABLE "new_Entry" RENAME TO "Entry";
+CREATE UNIQUE INDEX "Entry_user_id_date_key" ON "Entry"("user_id", "date");
+DROP TABLE "_entry_date";
+
+-- the search index is rebuilt by the server on startup, as for
+-- 20261018180000_reindex_entry_search
+INSERT INTO "entry_fts" ("entry_fts") VALUES ('delete-all');
+DROP TABLE "entry_search";
+CREATE TABLE "entry_search" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "user_id" INTEGER NOT NULL,
+    "date" TEXT NOT NULL,
+    "text" TEXT NOT NULL,
+    CONSTRAINT "entry_search_user_id_date_fkey" FOREIGN KEY ("user_id", "date") REFERENCES "Entry" ("user_id", "date") ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE UNIQUE INDEX "entry_search_user_id_date_key" ON "entry_search"("user_id", "date");
+
+CREATE TRIGGER "entry_search_ai" AFTER INSERT ON "entry_search" BEGIN
+    INSERT INTO "entry_fts" ("rowid", "text") VALUES (new."id", new."text");
+END;
+
+CREATE TRIGGER "entry_search_ad" AFTER DELETE ON "entry_search" BEGIN
+    INSERT INTO "entry_fts" ("entry_fts", "rowid", "text") VALUES ('delete', old."id", old."text");
+END;
+
+CREATE TRIGGER "entry_search_au" AFTER UPDATE ON "entry_search" BEGIN
+    INSERT INTO "entry_fts" ("entry_fts", "rowid", "text") VALUES ('delete', old."id", old."text");
+    INSERT INTO "entry_fts" ("rowid", "text") VALUES (new."id", new."text");
+END;
+PRAGMA foreign_keys=ON;
+PRAGMA defer_foreign_keys=OFF;
@@ -0,0 +1,157 @@
+-- the schema of the sqlite migrations up to 20261018210000_entry_date_as_text,
+-- see schema.prisma
+
+-- CreateTable
+CREATE TABLE "User" (
+    "id" BIGSERIAL NOT NULL,
+    "email" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "password" TEXT NOT NULL,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "daily_word_goal" BIGINT NOT NULL DEFAULT 750,
+    "timezone" TEXT NOT NULL DEFAULT 'UTC',
+
+    CONSTRAINT "User_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "Entry" (
+    "user_id" BIGINT NOT NULL,
+    "date" DATE NOT NULL,
+    "text" JSONB NOT NULL,
+    "word_count" BIGINT NOT NULL,
+    "revision" BIGINT NOT NULL DEFAULT 1,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "deleted_at" TIMESTAMPTZ,
+
+    CONSTRAINT "Entry_pkey" PRIMARY KEY ("user_id", "date")
+);
+
+-- CreateTable
+CREATE TABLE "entry_revision" (
+    "id" BIGSERIAL NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "date" DATE NOT NULL,
+    "text" JSONB NOT NULL,
+    "word_count" BIGINT NOT NULL,
+    "revision" BIGINT NOT NULL,
+    "saved_at" TIMESTAMPTZ NOT NULL,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "entry_revision_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "session" (
+    "id" BIGSERIAL NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "refresh_token_hash" TEXT NOT NULL,
+    "expires_at" TIMESTAMPTZ NOT NULL,
+    "revoked_at" TIMESTAMPTZ,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "session_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "password_reset_token" (
+    "id" BIGSERIAL NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "token_hash" TEXT NOT NULL,
+    "expires_at" TIMESTAMPTZ NOT NULL,
+    "used_at" TIMESTAMPTZ,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "password_reset_token_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "rate_limit_bucket" (
+    "key" TEXT NOT NULL,
+    "tokens" DOUBLE PRECISION NOT NULL,
+    "updated_at" DOUBLE PRECISION NOT NULL,
+
+    CONSTRAINT "rate_limit_bucket_pkey" PRIMARY KEY ("key")
+);
+
+-- CreateTable
+CREATE TABLE "user_totp" (
+    "user_id" BIGINT NOT NULL,
+    "secret" TEXT NOT NULL,
+    "enabled_at" TIMESTAMPTZ,
+    "last_used_step" BIGINT,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "user_totp_pkey" PRIMARY KEY ("user_id")
+);
+
+-- CreateTable
+CREATE TABLE "recovery_code" (
+    "id" BIGSERIAL NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "code_hash" TEXT NOT NULL,
+    "used_at" TIMESTAMPTZ,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "recovery_code_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "entry_search" (
+    "id" BIGSERIAL NOT NULL,
+    "user_id" BIGINT NOT NULL,
+    "date" DATE NOT NULL,
+    "text" TEXT NOT NULL,
+    -- not generated by prisma. 'simple' only lowercases, like the unicode61
+    -- tokenizer of the sqlite fts table, but keeps diacritics
+    -- ref: https://www.postgresql.org/docs/current/textsearch-tables.html
+    "search" TSVECTOR NOT NULL GENERATED ALWAYS AS (to_tsvector('simple', "text")) STORED,
+
+    CONSTRAINT "entry_search_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "User_email_key" ON "User"("email");
+
+-- CreateIndex
+CREATE INDEX "entry_revision_user_id_date_idx" ON "entry_revision"("user_id", "date");
+
+-- CreateIndex
+CREATE INDEX "session_user_id_idx" ON "session"("user_id");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "password_reset_token_token_hash_key" ON "password_reset_token"("token_hash");
+
+-- CreateIndex
+CREATE INDEX "password_reset_token_user_id_idx" ON "password_reset_token"("user_id");
+
+-- CreateIndex
+CREATE INDEX "recovery_code_user_id_idx" ON "recovery_code"("user_id");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "entry_search_user_id_date_key" ON "entry_search"("user_id", "date");
+
+-- not generated by prisma
+CREATE INDEX "entry_search_search_idx" ON "entry_search" USING GIN ("search");
+
+-- AddForeignKey
+ALTER TABLE "Entry" ADD CONSTRAINT "Entry_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "User"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "entry_revision" ADD CONSTRAINT "entry_revision_user_id_date_fkey" FOREIGN KEY ("user_id", "date") REFERENCES "Entry"("user_id", "date") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "session" ADD CONSTRAINT "session_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "User"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "password_reset_token" ADD CONSTRAINT "password_reset_token_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "User"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "user_totp" ADD CONSTRAINT "user_totp_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "User"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "recovery_code" ADD CONSTRAINT "recovery_code_user_id_fkey" FOREIGN KEY ("user_id") REFERENCES "User"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "entry_search" ADD CONSTRAINT "entry_search_user_id_date_fkey" FOREIGN KEY ("user_id", "date") REFERENCES "Entry"("user_id", "date") ON DELETE CASCADE ON UPDATE CASCADE;
@@ -0,0 +1,69 @@
+// one-off jobs for existing rows, run with `server <command>`, see `main`
+
+use time::Date;
+
+use crate::{db::Repository, tiptap::TiptapJsonContent};
+
+const BATCH_SIZE: i64 = 500;
+
+fn count_words(text: serde_json::Value) -> Option<i64> {
+    serde_json::from_value::<TiptapJsonContent>(text)
+        .ok()
+        .map(|content| content.count_words())
+}
+
+/// recomputes `word_count` of entries and entry revisions, e.g. after the way
+/// words are counted changed. returns how many rows were changed.
+pub async fn word_counts(repo: &dyn Repository) -> Result<u64, sqlx::Error> {
+    let mut updated = 0;
+
+    // user ids start at 1
+    let mut after = (0, Date::MIN);
+    loop {
+        let entries = repo
+            .list_all_entries_after_key(after.0, after.1, BATCH_SIZE)
+            .await?;
+        let Some(last) = entries.last() else {
+            break;
+        };
+        after = (last.user_id, last.date);
+        for entry in entries {
+            // unparsable entries keep their count
+            match count_words(entry.text) {
+                Some(word_count) if word_count != entry.word_count => {
+                    repo.update_entry_word_count_by_user_and_date(
+                        word_count,
+                        entry.user_id,
+                        entry.date,
+                    )
+                    .await?;
+                    updated += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut after = 0;
+    loop {
+        let revisions = repo
+            .list_entry_revisions_after_id(after, BATCH_SIZE)
+            .await?;
+        let Some(last) = revisions.last() else {
+            break;
+        };
+        after = last.id;
+        for revision in revisions {
+            match count_words(revision.text) {
+                Some(word_count) if word_count != revision.word_count => {
+                    repo.update_entry_revision_word_count_by_id(word_count, revision.id)
+                        .await?;
+                    updated += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(updated)
+}
@@ -1,18 +1,38 @@
+use std::{collections::HashMap, sync::Arc};
+
 use crate::{
-    datetime::AppDateTime,
-    db::{self, DbEntry, DbUser},
-    extractor::ValidatedJson,
+    datetime::AppDate,
+    db::{
+        self, DbEntry, DbEntryRevision, DbEntryRevisionSummary, DbEntrySearchResult, DbSession,
+        DbTrashedEntry, DbUser, DbUserTotp, EntryText, Repository,
+    },
+    env::Env,
+    export,
+    extractor::{EntryBody, ValidatedJson},
+    hasher::Hasher,
+    html,
+    import::{self, ConflictStrategy},
+    mailer::{self, Mail, Mailer},
+    middleware,
+    rate_limit::RateLimiter,
+    search,
+    stats::{self, Stats},
+    streak::{self, Streak},
     tiptap::TiptapJsonContent,
-    utils,
+    totp, utils,
 };
 use axum::{
     Extension,
-    extract::{Json, Path, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
 };
+use log::error;
 use serde::Deserialize;
 use serde_json::{Value, json};
-use sqlx::SqlitePool;
+use similar::{ChangeTag, TextDiff};
+use time::{Date, OffsetDateTime};
 use validator::Validate;
 
 /* ---------------------------------- root ---------------------------------- */
@@ -43,28 +63,57 @@ pub struct SignupInput {
 // signup handler with json input
 // ref: https://docs.rs/axum/0.8.4/axum/extract/index.html
 pub async fn signup(
-    State(pool): State<SqlitePool>,
+    State(repo): State<Arc<dyn Repository>>,
+    State(hasher): State<Hasher>,
+    State(mailer): State<Arc<dyn Mailer>>,
     ValidatedJson(input): ValidatedJson<SignupInput>,
 ) -> Result<StatusCode, StatusCode> {
-    let user_id = db::get_user_id_by_email(&pool, &input.email)
+    // hash before the lookup, so that both outcomes below take the same time
+    let hashed_password = hasher
+        .hash(&input.password)
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let user_id = repo
+        .get_user_id_by_email(&input.email)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     if user_id.is_some() {
-        // TODO: vulnerable to enumeration attack
+        // respond exactly like a successful signup, and tell the owner of the
+        // address instead, to prevent account enumeration
         // ref: https://cheatsheetseries.owasp.org/cheatsheets/Authentication_Cheat_Sheet.html#authentication-responses
         // ref: https://owasp.org/www-project-web-security-testing-guide/latest/4-Web_Application_Security_Testing/03-Identity_Management_Testing/04-Testing_for_Account_Enumeration_and_Guessable_User_Account
-        return Err(StatusCode::CONFLICT);
+        send_existing_account_mail(mailer, input.email);
+        return Ok(StatusCode::CREATED);
     }
 
-    let hashed_password =
-        utils::hash_password(&input.password).map_err(|_| StatusCode::BAD_REQUEST)?;
-
-    db::create_user(&pool, &input.email, &input.name, &hashed_password)
+    match repo
+        .create_user(&input.email, &input.name, &hashed_password)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    {
+        Ok(()) => Ok(StatusCode::CREATED),
+        // lost the race against a concurrent signup with the same email
+        Err(err) if db::is_unique_violation(&err) => Ok(StatusCode::CREATED),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
 
-    Ok(StatusCode::CREATED)
+fn send_existing_account_mail(mailer: Arc<dyn Mailer>, email: String) {
+    let client_base_url = Env::get().client_base_url.trim_end_matches('/');
+    let mail = Mail {
+        to: email,
+        subject: "Sign up attempt on 3pages".to_string(),
+        body: format!(
+            "Someone tried to create a 3pages account with this email, \
+            but you already have one.\n\n\
+            If it was you, log in at {}/login instead. \
+            Forgot your password? Reset it at {}/forgot-password\n\n\
+            If this wasn't you, you can ignore this mail.",
+            client_base_url, client_base_url
+        ),
+    };
+    mailer::send_in_background(mailer, mail);
 }
 
 /* --------------------------------- login ---------------------------------- */
@@ -83,100 +132,1294 @@ pub struct LoginInput {
 }
 
 pub async fn login(
-    State(pool): State<SqlitePool>,
+    State(repo): State<Arc<dyn Repository>>,
+    State(hasher): State<Hasher>,
     ValidatedJson(input): ValidatedJson<LoginInput>,
 ) -> Result<Json<Value>, StatusCode> {
-    let user = db::get_user_by_email(&pool, &input.email)
+    let user = repo
+        .get_user_by_email(&input.email)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(user) = user else {
+        // run argon2 anyway, so that unknown emails are not rejected faster
+        hasher
+            .verify_dummy(&input.password)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let valid = hasher
+        .verify(&input.password, &user.password)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !valid {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if hasher.needs_rehash(&user.password) {
+        rehash_password(&*repo, &hasher, user.id, &input.password).await;
+    }
+
+    // the second factor is checked by `login_totp`
+    let totp = repo
+        .get_user_totp_by_user(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if totp.is_some_and(|totp| totp.is_enabled()) {
+        let challenge_token = utils::create_totp_challenge_jwt(user.id)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Ok(Json(
+            json!({ "totpRequired": true, "challengeToken": challenge_token }),
+        ));
+    }
+
+    let (token, refresh_token) = create_session(&*repo, user.id).await?;
+
+    Ok(Json(
+        json!({ "token": token, "refreshToken": refresh_token, "user": user }),
+    ))
+}
+
+/// upgrades a hash made with outdated params, now that the password is known.
+/// failures are only logged, the old hash keeps working.
+async fn rehash_password(repo: &dyn Repository, hasher: &Hasher, user_id: i64, password: &str) {
+    let result = match hasher.hash(password).await {
+        Ok(new_hash) => repo
+            .update_user_password_by_id(&new_hash, user_id)
+            .await
+            .map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    if let Err(err) = result {
+        error!("Failed to rehash password of user {}: {}", user_id, err);
+    }
+}
+
+/// starts a new session, returns the access token and the refresh token
+async fn create_session(
+    repo: &dyn Repository,
+    user_id: i64,
+) -> Result<(String, String), StatusCode> {
+    let secret = utils::generate_token_secret();
+    let expires_at = OffsetDateTime::now_utc() + utils::REFRESH_TOKEN_TTL;
+    let session_id = repo
+        .create_session(user_id, &utils::hash_token(&secret), expires_at)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let token =
+        utils::create_jwt(user_id, session_id).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok((token, utils::create_refresh_token(session_id, &secret)))
+}
+
+/* ------------------------------- login totp ------------------------------- */
+
+#[derive(Deserialize)]
+pub struct LoginTotpInput {
+    #[serde(rename = "challengeToken")]
+    challenge_token: String,
+    code: Option<String>,
+    // instead of `code`, when the authenticator is lost
+    #[serde(rename = "recoveryCode")]
+    recovery_code: Option<String>,
+}
+
+pub async fn login_totp(
+    State(repo): State<Arc<dyn Repository>>,
+    State(limiter): State<RateLimiter>,
+    Json(input): Json<LoginTotpInput>,
+) -> Result<Response, StatusCode> {
+    let claims = utils::decode_totp_challenge_jwt(&input.challenge_token)
+        .ok_or(StatusCode::UNAUTHORIZED)?
+        .claims;
+
+    let user = repo
+        .get_user_by_id(claims.user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let totp = repo
+        .get_user_totp_by_user(user.id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .filter(|totp| totp.is_enabled())
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    if !utils::verify_password(&input.password, &user.password) {
+    if let Err(response) = verify_second_factor(&*repo, &limiter, user.id, &totp, &input).await {
+        return Ok(response);
+    }
+
+    let (token, refresh_token) = create_session(&*repo, user.id).await?;
+
+    Ok(
+        Json(json!({ "token": token, "refreshToken": refresh_token, "user": user }))
+            .into_response(),
+    )
+}
+
+/// wrong codes are counted per user, not per challenge, since every correct
+/// password gives a new challenge. the attempt is counted before verifying, so
+/// that concurrent guesses can't all get through.
+async fn verify_second_factor(
+    repo: &dyn Repository,
+    limiter: &RateLimiter,
+    user_id: i64,
+    totp: &DbUserTotp,
+    input: &LoginTotpInput,
+) -> Result<(), Response> {
+    if input.code.is_none() && input.recovery_code.is_none() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+
+    let key = format!("totp-failure:{}", user_id);
+    let wait = limiter
+        .take(&key, middleware::TOTP_FAILURE_BUCKET)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    if let Some(wait) = wait {
+        return Err(middleware::too_many_requests(wait));
+    }
+
+    let verified = match (&input.code, &input.recovery_code) {
+        (Some(code), _) => verify_totp_code(repo, user_id, totp, code).await,
+        (None, Some(recovery_code)) => {
+            let code_hash = utils::hash_token(&totp::normalize_recovery_code(recovery_code));
+            repo.use_recovery_code_by_user_and_hash(user_id, &code_hash)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        (None, None) => Err(StatusCode::BAD_REQUEST),
+    }
+    .map_err(IntoResponse::into_response)?;
+    if !verified {
+        return Err(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    limiter
+        .reset(&key)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+async fn verify_totp_code(
+    repo: &dyn Repository,
+    user_id: i64,
+    totp: &DbUserTotp,
+    code: &str,
+) -> Result<bool, StatusCode> {
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let Some(step) = totp::verify(&totp.secret, code, totp.last_used_step, now) else {
+        return Ok(false);
+    };
+
+    // fails if a concurrent request used a code of the same step, so every
+    // code is accepted only once
+    repo.update_user_totp_last_used_step_by_user(step, user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/* -------------------------------- refresh --------------------------------- */
+
+#[derive(Deserialize)]
+pub struct RefreshInput {
+    #[serde(rename = "refreshToken")]
+    refresh_token: String,
+}
+
+pub async fn refresh(
+    State(repo): State<Arc<dyn Repository>>,
+    Json(input): Json<RefreshInput>,
+) -> Result<Json<Value>, StatusCode> {
+    let (session_id, secret) =
+        utils::parse_refresh_token(&input.refresh_token).ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let session = repo
+        .get_session_by_id(session_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if !session.is_active() {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let presented_hash = utils::hash_token(secret);
+    if presented_hash != session.refresh_token_hash {
+        // an already rotated refresh token was used again, so either the client
+        // or an attacker holds a stolen copy. revoke the whole session.
+        // ref: https://datatracker.ietf.org/doc/html/rfc9700#name-refresh-token-protection
+        repo.revoke_session_by_id(session.id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let new_secret = utils::generate_token_secret();
+    let expires_at = OffsetDateTime::now_utc() + utils::REFRESH_TOKEN_TTL;
+    let rotated = repo
+        .rotate_session_refresh_token_by_id_and_hash(
+            &utils::hash_token(&new_secret),
+            expires_at,
+            session.id,
+            &presented_hash,
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !rotated {
+        // lost the race against a concurrent refresh or logout
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    let token = utils::create_jwt(user.id).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let token = utils::create_jwt(session.user_id, session.id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let refresh_token = utils::create_refresh_token(session.id, &new_secret);
 
-    Ok(Json(json!({ "token": token, "user": user })))
+    Ok(Json(
+        json!({ "token": token, "refreshToken": refresh_token }),
+    ))
 }
 
-/* ------------------------------- put entry -------------------------------- */
+/* --------------------------------- logout --------------------------------- */
 
-#[derive(Deserialize, Debug)]
-pub struct PutEntryInput {
-    text: TiptapJsonContent,
+pub async fn logout(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(session): Extension<DbSession>,
+) -> Result<StatusCode, StatusCode> {
+    repo.revoke_session_by_id(session.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn put_entry(
-    State(pool): State<SqlitePool>,
+/* ------------------------------- logout all ------------------------------- */
+
+pub async fn logout_all(
+    State(repo): State<Arc<dyn Repository>>,
     Extension(user): Extension<DbUser>,
-    Path(date): Path<String>,
-    Json(input): Json<PutEntryInput>,
 ) -> Result<StatusCode, StatusCode> {
-    let date = AppDateTime::from_iso_string(&date).map_err(|_| StatusCode::BAD_REQUEST)?;
-    let existing_entry = db::check_entry_exists_by_user_and_date(&pool, user.id, date.into())
+    repo.revoke_sessions_by_user(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/* ---------------------------- forgot password ----------------------------- */
+
+#[derive(Validate, Deserialize)]
+pub struct ForgotPasswordInput {
+    #[validate(email(message = "Invalid email"))]
+    #[serde(with = "crate::utils::trimmed_string")]
+    email: String,
+}
+
+pub async fn forgot_password(
+    State(repo): State<Arc<dyn Repository>>,
+    State(mailer): State<Arc<dyn Mailer>>,
+    ValidatedJson(input): ValidatedJson<ForgotPasswordInput>,
+) -> Result<StatusCode, StatusCode> {
+    let user = repo
+        .get_user_by_email(&input.email)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let word_count = input.text.count_words();
-    let text_json = serde_json::to_value(&input.text).map_err(|_| StatusCode::BAD_REQUEST)?;
+    // respond the same way whether the account exists or not
+    // ref: https://cheatsheetseries.owasp.org/cheatsheets/Forgot_Password_Cheat_Sheet.html#forgot-password-request
+    let Some(user) = user else {
+        return Ok(StatusCode::ACCEPTED);
+    };
 
-    if existing_entry.is_none() {
-        db::create_entry(&pool, user.id, date.into(), text_json, word_count)
+    // only the most recently requested link works
+    repo.delete_unused_password_reset_tokens_by_user(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let secret = utils::generate_token_secret();
+    let expires_at = OffsetDateTime::now_utc() + utils::PASSWORD_RESET_TOKEN_TTL;
+    repo.create_password_reset_token(user.id, &utils::hash_token(&secret), expires_at)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let link = format!(
+        "{}/reset-password?token={}",
+        Env::get().client_base_url.trim_end_matches('/'),
+        secret
+    );
+    let mail = Mail {
+        to: user.email,
+        subject: "Reset your 3pages password".to_string(),
+        body: format!(
+            "Someone requested a password reset for your 3pages account.\n\n\
+            Open the link below to choose a new password. \
+            It expires in 1 hour and can only be used once.\n\n\
+            {}\n\n\
+            If this wasn't you, you can ignore this mail.",
+            link
+        ),
+    };
+    mailer::send_in_background(mailer, mail);
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/* ----------------------------- reset password ----------------------------- */
+
+#[derive(Validate, Deserialize)]
+pub struct ResetPasswordInput {
+    token: String,
+    #[validate(length(
+        min = 8,
+        max = 40,
+        message = "Password must be between 8 and 40 characters long"
+    ))]
+    password: String,
+}
+
+pub async fn reset_password(
+    State(repo): State<Arc<dyn Repository>>,
+    State(hasher): State<Hasher>,
+    ValidatedJson(input): ValidatedJson<ResetPasswordInput>,
+) -> Result<StatusCode, StatusCode> {
+    let reset_token = repo
+        .get_password_reset_token_by_hash(&utils::hash_token(&input.token))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    if !reset_token.is_usable() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // hashed before the token is used, so that a failure doesn't burn the link
+    let hashed_password = hasher
+        .hash(&input.password)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // together with the password, so that concurrent requests can't both
+    // succeed
+    let used = repo
+        .use_password_reset_token_by_id(reset_token.id, reset_token.user_id, &hashed_password)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !used {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // whoever knew the old password should not stay logged in
+    repo.revoke_sessions_by_user(reset_token.user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/* ----------------------------------- me ----------------------------------- */
+
+pub async fn get_me(Extension(user): Extension<DbUser>) -> Json<DbUser> {
+    Json(user)
+}
+
+#[derive(Validate, Deserialize)]
+pub struct UpdateMeInput {
+    #[validate(length(min = 1, message = "Name is required"))]
+    #[serde(default, with = "crate::utils::optional_trimmed_string")]
+    name: Option<String>,
+    #[validate(email(message = "Invalid email"))]
+    #[serde(default, with = "crate::utils::optional_trimmed_string")]
+    email: Option<String>,
+    #[validate(length(
+        min = 8,
+        max = 40,
+        message = "Password must be between 8 and 40 characters long"
+    ))]
+    #[serde(rename = "newPassword")]
+    new_password: Option<String>,
+    #[validate(range(
+        min = 1,
+        max = 100_000,
+        message = "Daily word goal must be between 1 and 100000"
+    ))]
+    #[serde(rename = "dailyWordGoal")]
+    daily_word_goal: Option<i64>,
+    #[validate(custom(
+        function = "crate::utils::validate_timezone",
+        message = "Unknown timezone"
+    ))]
+    timezone: Option<String>,
+    // required when changing email or password
+    #[serde(rename = "currentPassword")]
+    current_password: Option<String>,
+}
+
+pub async fn update_me(
+    State(repo): State<Arc<dyn Repository>>,
+    State(hasher): State<Hasher>,
+    Extension(user): Extension<DbUser>,
+    Extension(session): Extension<DbSession>,
+    ValidatedJson(input): ValidatedJson<UpdateMeInput>,
+) -> Result<Json<DbUser>, StatusCode> {
+    // a stolen access token alone should not be enough to take over the account
+    if input.email.is_some() || input.new_password.is_some() {
+        let current_password = input
+            .current_password
+            .as_deref()
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        let valid = hasher
+            .verify(current_password, &user.password)
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    } else {
-        db::update_entry_text_by_user_and_date(&pool, text_json, word_count, user.id, date.into())
+        if !valid {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    let hashed_password = match &input.new_password {
+        Some(new_password) => Some(
+            hasher
+                .hash(new_password)
+                .await
+                .map_err(|_| StatusCode::BAD_REQUEST)?,
+        ),
+        None => None,
+    };
+
+    repo.update_user_by_id(
+        input.name.as_deref(),
+        input.email.as_deref(),
+        hashed_password.as_deref(),
+        input.daily_word_goal,
+        input.timezone.as_deref(),
+        user.id,
+    )
+    .await
+    .map_err(|err| {
+        if db::is_unique_violation(&err) {
+            StatusCode::CONFLICT
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })?;
+
+    if hashed_password.is_some() {
+        // keep the current device logged in, log out everywhere else
+        repo.revoke_other_sessions_by_user(user.id, session.id)
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     }
-    Ok(StatusCode::OK)
+
+    let user = repo
+        .get_user_by_id(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(user))
 }
 
-/* -------------------------- get all entry dates --------------------------- */
+#[derive(Deserialize)]
+pub struct DeleteMeInput {
+    password: String,
+}
 
-pub async fn get_all_entry_dates(
-    State(pool): State<SqlitePool>,
+pub async fn delete_me(
+    State(repo): State<Arc<dyn Repository>>,
+    State(hasher): State<Hasher>,
+    Extension(user): Extension<DbUser>,
+    Json(input): Json<DeleteMeInput>,
+) -> Result<StatusCode, StatusCode> {
+    let valid = hasher
+        .verify(&input.password, &user.password)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !valid {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    repo.delete_user_by_id(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/* -------------------------------- me totp --------------------------------- */
+
+pub async fn setup_totp(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+) -> Result<Json<Value>, StatusCode> {
+    let existing = repo
+        .get_user_totp_by_user(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if existing.is_some_and(|totp| totp.is_enabled()) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    // stays pending until confirmed with a first code by `enable_totp`
+    let secret = totp::generate_secret();
+    repo.upsert_pending_user_totp(user.id, &secret)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let otpauth_uri =
+        totp::otpauth_uri(&secret, &user.email).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(json!({ "secret": secret, "otpauthUri": otpauth_uri })))
+}
+
+#[derive(Deserialize)]
+pub struct EnableTotpInput {
+    code: String,
+}
+
+pub async fn enable_totp(
+    State(repo): State<Arc<dyn Repository>>,
     Extension(user): Extension<DbUser>,
-) -> Result<Json<Vec<String>>, StatusCode> {
-    let dates: Result<Vec<String>, _> = db::list_entry_dates_by_user(&pool, user.id)
+    Json(input): Json<EnableTotpInput>,
+) -> Result<Json<Value>, StatusCode> {
+    let pending = repo
+        .get_user_totp_by_user(user.id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if pending.is_enabled() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let step =
+        totp::verify(&pending.secret, &input.code, None, now).ok_or(StatusCode::BAD_REQUEST)?;
+    let enabled = repo
+        .enable_user_totp_by_user(step, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !enabled {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let recovery_codes = create_recovery_codes(&*repo, user.id).await?;
+
+    Ok(Json(json!({ "recoveryCodes": recovery_codes })))
+}
+
+/// returns the plain codes, which are shown to the user only this once
+async fn create_recovery_codes(
+    repo: &dyn Repository,
+    user_id: i64,
+) -> Result<Vec<String>, StatusCode> {
+    let recovery_codes = totp::generate_recovery_codes();
+    let code_hashes = recovery_codes
         .iter()
-        .map(|date| date.to_yyyy_mm_dd_string())
-        .collect();
-    let dates = dates.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map(|code| utils::hash_token(&totp::normalize_recovery_code(code)))
+        .collect::<Vec<_>>();
+    repo.replace_recovery_codes_by_user(user_id, &code_hashes)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(recovery_codes)
+}
+
+#[derive(Deserialize)]
+pub struct TotpPasswordInput {
+    password: String,
+}
+
+pub async fn regenerate_recovery_codes(
+    State(repo): State<Arc<dyn Repository>>,
+    State(hasher): State<Hasher>,
+    Extension(user): Extension<DbUser>,
+    Json(input): Json<TotpPasswordInput>,
+) -> Result<Json<Value>, StatusCode> {
+    let valid = hasher
+        .verify(&input.password, &user.password)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !valid {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let totp = repo
+        .get_user_totp_by_user(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !totp.is_some_and(|totp| totp.is_enabled()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let recovery_codes = create_recovery_codes(&*repo, user.id).await?;
+
+    Ok(Json(json!({ "recoveryCodes": recovery_codes })))
+}
+
+pub async fn disable_totp(
+    State(repo): State<Arc<dyn Repository>>,
+    State(hasher): State<Hasher>,
+    Extension(user): Extension<DbUser>,
+    Json(input): Json<TotpPasswordInput>,
+) -> Result<StatusCode, StatusCode> {
+    let valid = hasher
+        .verify(&input.password, &user.password)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !valid {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    repo.delete_user_totp_by_user(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/* ------------------------------- put entry -------------------------------- */
+
+pub async fn put_entry(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Path(date): Path<String>,
+    headers: HeaderMap,
+    EntryBody(text): EntryBody,
+) -> Result<Response, StatusCode> {
+    let date = AppDate::from_yyyy_mm_dd_string(&date).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let existing_entry = repo
+        .get_entry_by_user_and_date(user.id, date.into())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // without `If-Match` the entry is overwritten, like before
+    let if_match = headers
+        .get(header::IF_MATCH)
+        .map(|value| value.to_str().map_err(|_| StatusCode::BAD_REQUEST))
+        .transpose()?;
+    if let Some(if_match) = if_match {
+        let revision = existing_entry.as_ref().map(|entry| entry.revision);
+        if !utils::if_match_allows(if_match, revision) {
+            return Ok(entry_precondition_failed(existing_entry));
+        }
+    }
+
+    let text = EntryText::new(&text).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let revision = match existing_entry {
+        // replaces an entry in the trash, if any
+        None => repo
+            .create_entry(user.id, date.into(), text)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        Some(entry) => {
+            // only overwrite the revision which was checked above
+            let expected_revision = if_match.map(|_| entry.revision);
+            repo.update_entry_text_by_user_and_date(
+                text,
+                user.id,
+                date.into(),
+                expected_revision,
+                Env::get().entry_revision_interval,
+            )
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        }
+    };
+
+    let Some(revision) = revision else {
+        // created, changed or deleted by another request in the meantime
+        let current_entry = repo
+            .get_entry_by_user_and_date(user.id, date.into())
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Ok(entry_precondition_failed(current_entry));
+    };
+
+    Ok((
+        StatusCode::OK,
+        [(header::ETAG, utils::entry_etag(revision))],
+    )
+        .into_response())
+}
+
+/// responds with the current version of the entry, so that the client can
+/// show it to the user instead of silently losing either version
+fn entry_precondition_failed(entry: Option<DbEntry>) -> Response {
+    match entry {
+        Some(entry) => (
+            StatusCode::PRECONDITION_FAILED,
+            [(header::ETAG, utils::entry_etag(entry.revision))],
+            Json(entry),
+        )
+            .into_response(),
+        None => StatusCode::PRECONDITION_FAILED.into_response(),
+    }
+}
+
+/* -------------------------- get all entry dates --------------------------- */
+
+pub async fn get_all_entry_dates(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+) -> Result<Json<Vec<AppDate>>, StatusCode> {
+    let dates = repo
+        .list_entry_dates_by_user(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(dates))
 }
 
+/* ------------------------------ list entries ------------------------------ */
+
+const LIST_DEFAULT_LIMIT: i64 = 100;
+const LIST_MAX_LIMIT: i64 = 366;
+/// in characters
+const PREVIEW_LENGTH: usize = 140;
+
+#[derive(Deserialize)]
+pub struct ListEntriesQuery {
+    from: Option<AppDate>,
+    to: Option<AppDate>,
+    /// `nextCursor` of the previous page
+    cursor: Option<AppDate>,
+    limit: Option<i64>,
+}
+
+/// metadata and a preview of the entries from `from` to `to`, in date order.
+/// `nextCursor` is `null` on the last page.
+pub async fn list_entries(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Query(query): Query<ListEntriesQuery>,
+) -> Result<Json<Value>, StatusCode> {
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query
+        .limit
+        .unwrap_or(LIST_DEFAULT_LIMIT)
+        .clamp(1, LIST_MAX_LIMIT);
+
+    // one more than the limit, to know if there is a next page. twice the
+    // preview length, since whitespace is collapsed afterwards.
+    let mut entries = repo
+        .list_entry_summaries_by_user_and_date_range(
+            user.id,
+            query.from.map(Into::into),
+            query.to.map(Into::into),
+            query.cursor.map(Into::into),
+            2 * PREVIEW_LENGTH as i64,
+            limit + 1,
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let next_cursor = if entries.len() as i64 > limit {
+        entries.truncate(limit as usize);
+        entries.last().map(|entry| entry.date)
+    } else {
+        None
+    };
+    for entry in &mut entries {
+        entry.preview = utils::preview(&entry.preview, PREVIEW_LENGTH);
+    }
+
+    Ok(Json(
+        json!({ "entries": entries, "nextCursor": next_cursor }),
+    ))
+}
+
 /* --------------------------- get entry by date ---------------------------- */
 
 pub async fn get_entry_by_date(
-    State(pool): State<SqlitePool>,
+    State(repo): State<Arc<dyn Repository>>,
     Extension(user): Extension<DbUser>,
     Path(date): Path<String>,
-) -> Result<Json<DbEntry>, StatusCode> {
-    let date = AppDateTime::from_iso_string(&date).map_err(|_| StatusCode::BAD_REQUEST)?;
-    let entry = db::get_entry_by_user_and_date(&pool, user.id, date.into())
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    // the router can't match `{date}.md`, so it is part of the date
+    // ref: https://docs.rs/axum/0.8.4/axum/struct.Router.html#wildcards
+    let (date, is_markdown) = match date.strip_suffix(".md") {
+        Some(date) => (date, true),
+        None => (date.as_str(), false),
+    };
+    let date = AppDate::from_yyyy_mm_dd_string(date).map_err(|_| StatusCode::BAD_REQUEST)?;
+    entry_response(&*repo, user.id, date, is_markdown, &headers).await
+}
+
+/// the current day in the user's timezone, with `Content-Location` telling
+/// which day that is, also if there is no entry yet
+pub async fn get_today_entry(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    headers: HeaderMap,
+) -> Response {
+    let date = user.today();
+    let mut response = entry_response(&*repo, user.id, date, false, &headers)
+        .await
+        .into_response();
+    if let Ok(location) = HeaderValue::from_str(&format!("/api/entry/{}", date)) {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_LOCATION, location);
+    }
+    response
+}
+
+async fn entry_response(
+    repo: &dyn Repository,
+    user_id: i64,
+    date: AppDate,
+    is_markdown: bool,
+    headers: &HeaderMap,
+) -> Result<Response, StatusCode> {
+    let entry = repo
+        .get_entry_by_user_and_date(user_id, date.into())
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    let etag = utils::entry_etag(entry.revision);
+
+    // the client asks for json, browsers opening the url for html
+    let is_html = !is_markdown
+        && headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("text/html"));
 
-    Ok(Json(entry))
+    if is_markdown || is_html {
+        let content = serde_json::from_value::<TiptapJsonContent>(entry.text)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if is_markdown {
+            return Ok((
+                [
+                    (
+                        header::CONTENT_TYPE,
+                        "text/markdown; charset=utf-8".to_string(),
+                    ),
+                    (header::ETAG, etag),
+                ],
+                content.to_markdown(),
+            )
+                .into_response());
+        }
+        let title = date.to_string();
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/html; charset=utf-8".to_string()),
+                (header::ETAG, etag),
+                // the text is escaped already, this is a second line of defense
+                (
+                    header::CONTENT_SECURITY_POLICY,
+                    "default-src 'none'; style-src 'unsafe-inline'".to_string(),
+                ),
+                (header::VARY, header::ACCEPT.to_string()),
+            ],
+            html::entry_page(&title, &content),
+        )
+            .into_response());
+    }
+
+    // sent back as `If-Match` when saving
+    Ok((
+        [
+            (header::ETAG, etag),
+            (header::VARY, header::ACCEPT.to_string()),
+        ],
+        Json(entry),
+    )
+        .into_response())
 }
 
 /* -------------------------- delete entry by date -------------------------- */
 
+/// moves the entry to the trash, see `purge_trashed_entry` for deleting it
 pub async fn delete_entry_by_date(
-    State(pool): State<SqlitePool>,
+    State(repo): State<Arc<dyn Repository>>,
     Extension(user): Extension<DbUser>,
     Path(date): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
-    let date = AppDateTime::from_iso_string(&date).map_err(|_| StatusCode::BAD_REQUEST)?;
-    db::delete_entry_by_user_and_date(&pool, user.id, date.into())
+    let date = AppDate::from_yyyy_mm_dd_string(&date).map_err(|_| StatusCode::BAD_REQUEST)?;
+    repo.trash_entry_by_user_and_date(user.id, date.into())
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/* ---------------------------------- trash --------------------------------- */
+
+pub async fn list_trashed_entries(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+) -> Result<Json<Vec<DbTrashedEntry>>, StatusCode> {
+    let entries = repo
+        .list_trashed_entries_by_user(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(entries))
+}
+
+pub async fn restore_trashed_entry(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Path(date): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let date = AppDate::from_yyyy_mm_dd_string(&date).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let restored = repo
+        .restore_entry_by_user_and_date(user.id, date.into())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !restored {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn purge_trashed_entry(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Path(date): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let date = AppDate::from_yyyy_mm_dd_string(&date).map_err(|_| StatusCode::BAD_REQUEST)?;
+    repo.purge_entry_by_user_and_date(user.id, date.into())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn purge_all_trashed_entries(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+) -> Result<StatusCode, StatusCode> {
+    repo.purge_entries_by_user(user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/* ---------------------------- entry revisions ----------------------------- */
+
+pub async fn list_entry_revisions(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Path(date): Path<String>,
+) -> Result<Json<Vec<DbEntryRevisionSummary>>, StatusCode> {
+    let date = AppDate::from_yyyy_mm_dd_string(&date).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let revisions = repo
+        .list_entry_revisions_by_user_and_date(user.id, date.into())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(revisions))
+}
+
+async fn find_entry_revision(
+    repo: &dyn Repository,
+    user_id: i64,
+    date: &str,
+    id: i64,
+) -> Result<(AppDate, DbEntryRevision), StatusCode> {
+    let date = AppDate::from_yyyy_mm_dd_string(date).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let revision = repo
+        .get_entry_revision_by_user_and_date_and_id(user_id, date.into(), id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok((date, revision))
+}
+
+pub async fn get_entry_revision(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Path((date, id)): Path<(String, i64)>,
+) -> Result<Json<DbEntryRevision>, StatusCode> {
+    let (_, revision) = find_entry_revision(&*repo, user.id, &date, id).await?;
+    Ok(Json(revision))
+}
+
+/// line based diff of the plain text, from the revision to the current text
+pub async fn diff_entry_revision(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Path((date, id)): Path<(String, i64)>,
+) -> Result<Json<Value>, StatusCode> {
+    let (date, revision) = find_entry_revision(&*repo, user.id, &date, id).await?;
+    let entry = repo
+        .get_entry_by_user_and_date(user.id, date.into())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let to_plain_text = |text: Value| {
+        serde_json::from_value::<TiptapJsonContent>(text)
+            .map(|content| content.to_plain_text())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    };
+    let old_text = to_plain_text(revision.text)?;
+    let new_text = to_plain_text(entry.text)?;
+
+    // ref: https://docs.rs/similar/2.7.0/similar/struct.TextDiff.html
+    let changes = TextDiff::from_lines(&old_text, &new_text)
+        .iter_all_changes()
+        .map(|change| {
+            let tag = match change.tag() {
+                ChangeTag::Equal => "equal",
+                ChangeTag::Delete => "delete",
+                ChangeTag::Insert => "insert",
+            };
+            json!({ "tag": tag, "value": change.value() })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!({
+        "revisionId": revision.id,
+        "revision": entry.revision,
+        "changes": changes,
+    })))
+}
+
+/// makes the revision the current text. the replaced text is kept as a
+/// revision as well, so restoring can be undone.
+pub async fn restore_entry_revision(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Path((date, id)): Path<(String, i64)>,
+) -> Result<Response, StatusCode> {
+    let (date, revision) = find_entry_revision(&*repo, user.id, &date, id).await?;
+    let content = serde_json::from_value::<TiptapJsonContent>(revision.text)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let text = EntryText::new(&content).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    repo.update_entry_text_by_user_and_date(text, user.id, date.into(), None, time::Duration::ZERO)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let entry = repo
+        .get_entry_by_user_and_date(user.id, date.into())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok((
+        [(header::ETAG, utils::entry_etag(entry.revision))],
+        Json(entry),
+    )
+        .into_response())
+}
+
+/* ------------------------------ search entries ---------------------------- */
+
+const SEARCH_DEFAULT_LIMIT: i64 = 20;
+const SEARCH_MAX_LIMIT: i64 = 100;
+
+#[derive(Deserialize, Debug)]
+pub struct SearchEntriesQuery {
+    q: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// best matches first, see `search::SearchQuery` for the query syntax
+pub async fn search_entries(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Query(query): Query<SearchEntriesQuery>,
+) -> Result<Json<Vec<DbEntrySearchResult>>, StatusCode> {
+    let search_query = search::SearchQuery::parse(&query.q).ok_or(StatusCode::BAD_REQUEST)?;
+    let limit = query
+        .limit
+        .unwrap_or(SEARCH_DEFAULT_LIMIT)
+        .clamp(1, SEARCH_MAX_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let mut results = repo
+        .search_entries_by_user(user.id, &search_query, limit, offset)
+        .await
+        .map_err(|err| {
+            if db::is_fts_syntax_error(&err) {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+    for result in &mut results {
+        result.snippet = search::snippet_to_html(&result.snippet);
+    }
+
+    Ok(Json(results))
+}
+
+/* --------------------------------- export --------------------------------- */
+
+/// every entry as markdown and tiptap json, with a manifest, see `export`
+pub async fn export_entries(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+) -> Result<Response, StatusCode> {
+    let filename = export::filename(OffsetDateTime::now_utc())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        export::export_body(repo, user),
+    )
+        .into_response())
+}
+
+/* --------------------------------- import --------------------------------- */
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    conflict: Option<ConflictStrategy>,
+}
+
+/// a zip or a day one json export, see `import`
+pub async fn import_entries(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<Response, StatusCode> {
+    let parsed = match import::parse(&body).await {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            let body = Json(json!({ "error": err.to_string() }));
+            return Ok((StatusCode::BAD_REQUEST, body).into_response());
+        }
+    };
+
+    let mut results = parsed.failed;
+    results.extend(
+        import::import_entries(
+            &*repo,
+            user.id,
+            parsed.entries,
+            query.conflict.unwrap_or_default(),
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+    );
+
+    let mut counts = HashMap::new();
+    for result in &results {
+        *counts.entry(&result.status).or_insert(0) += 1;
+    }
+
+    Ok(Json(json!({ "counts": counts, "results": results })).into_response())
+}
+
+/* ---------------------------------- stats --------------------------------- */
+
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    from: Option<AppDate>,
+    to: Option<AppDate>,
+}
+
+/// totals and histograms over all entries, and the words per day from `from`
+/// to `to`, see `stats::date_range` for the defaults
+pub async fn get_stats(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Stats>, StatusCode> {
+    let (from, to) = stats::date_range(
+        query.from.map(|date| *date),
+        query.to.map(|date| *date),
+        *user.today(),
+    )
+    .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let stats = stats::get_stats(&*repo, user.id, from, to)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(stats))
+}
+
+/* --------------------------------- streak --------------------------------- */
+
+/// see `streak::compute` for which days count. today is the current day in
+/// the user's timezone.
+pub async fn get_streak(
+    State(repo): State<Arc<dyn Repository>>,
+    Extension(user): Extension<DbUser>,
+) -> Result<Json<Streak>, StatusCode> {
+    let goal_met_days: Vec<Date> = repo
+        .list_goal_met_entry_dates_by_user(user.id, user.daily_word_goal)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .iter()
+        .map(|date| **date)
+        .collect();
+
+    Ok(Json(streak::compute(
+        user.daily_word_goal,
+        &goal_met_days,
+        *user.today(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::tests::{create_user, sqlite_repository},
+        rate_limit::MemoryStore,
+    };
+
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    fn code_input(code: &str) -> LoginTotpInput {
+        LoginTotpInput {
+            challenge_token: String::new(),
+            code: Some(code.to_string()),
+            recovery_code: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn wrong_totp_codes_lock_the_user() {
+        let repo = sqlite_repository().await;
+        let limiter = RateLimiter::new(Arc::new(MemoryStore::default()));
+        let user_id = create_user(&repo, "a@example.com").await;
+        repo.upsert_pending_user_totp(user_id, RFC_SECRET)
+            .await
+            .unwrap();
+        repo.enable_user_totp_by_user(0, user_id).await.unwrap();
+        let totp = repo.get_user_totp_by_user(user_id).await.unwrap().unwrap();
+
+        // wrong codes are counted across challenges
+        for _ in 0..5 {
+            let response = verify_second_factor(&repo, &limiter, user_id, &totp, &code_input("x"))
+                .await
+                .unwrap_err();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        let code = totp::generate(RFC_SECRET, OffsetDateTime::now_utc().unix_timestamp());
+        let response = verify_second_factor(&repo, &limiter, user_id, &totp, &code_input(&code))
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+        // the correct code still works once the lock is lifted
+        limiter
+            .reset(&format!("totp-failure:{}", user_id))
+            .await
+            .unwrap();
+        verify_second_factor(&repo, &limiter, user_id, &totp, &code_input(&code))
+            .await
+            .unwrap();
+    }
+}
@@ -1,9 +1,10 @@
-use std::ops::Deref;
+use std::{fmt, ops::Deref};
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Visitor};
 use time::{
     Date, OffsetDateTime, Time, format_description::well_known::Iso8601, macros::format_description,
 };
+use time_tz::{OffsetDateTimeExt, Tz};
 
 #[derive(Debug, Clone, Copy)]
 pub struct AppDateTime(pub OffsetDateTime);
@@ -25,16 +26,6 @@ impl AppDateTime {
     pub fn to_yyyy_mm_dd_string(self) -> Result<String, time::error::Format> {
         self.format(format_description!("[year]-[month]-[day]"))
     }
-
-    pub fn serialize_to_yyyy_mm_dd_string<S: Serializer>(
-        &self,
-        serializer: S,
-    ) -> Result<S::Ok, S::Error> {
-        let formatted_date = self
-            .to_yyyy_mm_dd_string()
-            .map_err(serde::ser::Error::custom)?;
-        serializer.serialize_str(&formatted_date)
-    }
 }
 
 impl Deref for AppDateTime {
@@ -90,6 +81,87 @@ impl From<AppDateTime> for time::OffsetDateTime {
     }
 }
 
+/// a calendar day, like the date of an entry. unlike `AppDateTime`, it has no
+/// offset, so the same day can't be written in different ways.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AppDate(pub Date);
+
+impl AppDate {
+    /// only `YYYY-MM-DD`, a date time would be ambiguous about the day
+    pub fn from_yyyy_mm_dd_string(date_str: &str) -> Result<Self, time::error::Parse> {
+        Date::parse(date_str, format_description!("[year]-[month]-[day]")).map(AppDate)
+    }
+
+    /// the day of `datetime` on a wall clock in `tz`, whatever its offset is at
+    /// the time, e.g. during daylight saving time
+    pub fn in_timezone(datetime: OffsetDateTime, tz: &Tz) -> Self {
+        AppDate(datetime.to_timezone(tz).date())
+    }
+
+    pub fn today(tz: &Tz) -> Self {
+        Self::in_timezone(OffsetDateTime::now_utc(), tz)
+    }
+}
+
+impl Deref for AppDate {
+    type Target = Date;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// `YYYY-MM-DD`
+impl fmt::Display for AppDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Serialize for AppDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AppDate {
+    fn deserialize<D>(deserializer: D) -> Result<AppDate, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(AppDateVisitor)
+    }
+}
+
+struct AppDateVisitor;
+
+impl<'de> Visitor<'de> for AppDateVisitor {
+    type Value = AppDate;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a `YYYY-MM-DD` date")
+    }
+
+    fn visit_str<E>(self, date_str: &str) -> Result<AppDate, E>
+    where
+        E: serde::de::Error,
+    {
+        AppDate::from_yyyy_mm_dd_string(date_str).map_err(E::custom)
+    }
+}
+
+impl From<Date> for AppDate {
+    fn from(date: Date) -> Self {
+        AppDate(date)
+    }
+}
+
+impl From<AppDate> for Date {
+    fn from(date: AppDate) -> Self {
+        date.0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,4 +234,52 @@ mod tests {
         assert_eq!(date.nanosecond(), 0);
         assert_eq!(date.offset(), offset!(UTC));
     }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct D {
+        date: AppDate,
+    }
+
+    #[test]
+    fn test_date_serialization_roundtrip() {
+        let json = serde_json::from_str::<D>(r#"{"date": "2025-07-26"}"#).unwrap();
+        assert_eq!(json.date, AppDate(time::macros::date!(2025 - 07 - 26)));
+        assert_eq!(
+            serde_json::to_string(&json).unwrap(),
+            r#"{"date":"2025-07-26"}"#
+        );
+    }
+
+    #[test]
+    fn test_date_rejects_date_times() {
+        assert!(AppDate::from_yyyy_mm_dd_string("2025-07-26").is_ok());
+        for input in [
+            "2025-07-26T23:30:00-08:00",
+            "2025-07-26T00:00:00Z",
+            "2025-7-26",
+            "2025-02-30",
+            "today",
+        ] {
+            assert!(AppDate::from_yyyy_mm_dd_string(input).is_err(), "{}", input);
+        }
+        assert!(serde_json::from_str::<D>(r#"{"date": "2025-07-26T00:00:00Z"}"#).is_err());
+    }
+
+    #[test]
+    fn test_date_in_timezone_across_dst() {
+        let tz = time_tz::timezones::db::america::NEW_YORK;
+        let date = |utc: &str| {
+            let datetime = OffsetDateTime::parse(utc, &Iso8601::DEFAULT).unwrap();
+            AppDate::in_timezone(datetime, tz).to_string()
+        };
+        // -04:00 before, -05:00 after 2025-11-02 02:00
+        assert_eq!(date("2025-11-02T03:59:00Z"), "2025-11-01");
+        assert_eq!(date("2025-11-02T04:00:00Z"), "2025-11-02");
+        assert_eq!(date("2025-11-03T04:30:00Z"), "2025-11-02");
+        assert_eq!(date("2025-11-03T05:00:00Z"), "2025-11-03");
+        // -05:00 before, -04:00 after 2025-03-09 02:00
+        assert_eq!(date("2025-03-09T04:59:00Z"), "2025-03-08");
+        assert_eq!(date("2025-03-10T03:59:00Z"), "2025-03-09");
+        assert_eq!(date("2025-03-10T04:00:00Z"), "2025-03-10");
+    }
 }
@@ -1,31 +1,371 @@
 // naming convention: [Action]_[Entity]_[By_Clause]
 
-use crate::datetime::AppDateTime;
+mod postgres;
+mod sqlite;
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::sync::Arc;
+
+use crate::{
+    datetime::{AppDate, AppDateTime},
+    rate_limit::{BucketState, TokenBucket},
+    search::SearchQuery,
+    tiptap::TiptapJsonContent,
+};
+use async_trait::async_trait;
 use serde::Serialize;
 use serde_json::Value;
-use sqlx::{Error, SqlitePool, query_file, query_file_as};
-use time::OffsetDateTime;
+use sqlx::{Error, FromRow, PgPool, SqlitePool};
+use time::{Date, OffsetDateTime};
+use time_tz::timezones;
+
+pub use postgres::PostgresRepository;
+pub use sqlite::SqliteRepository;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
 
-pub async fn get_user_id_by_email(pool: &SqlitePool, email: &str) -> Result<Option<i64>, Error> {
-    let record = query_file!("src/sql/get_user_id_by_email.sql", email)
-        .fetch_optional(pool)
-        .await?;
-    Ok(record.map(|r| r.id))
+impl Backend {
+    /// by the scheme of the url, e.g. `sqlite:data.db` or
+    /// `postgres://localhost/3pages`
+    pub fn from_url(database_url: &str) -> Result<Self, Error> {
+        match database_url.split_once(':').map(|(scheme, _)| scheme) {
+            Some("sqlite") => Ok(Backend::Sqlite),
+            Some("postgres" | "postgresql") => Ok(Backend::Postgres),
+            _ => Err(Error::Configuration(
+                "DATABASE_URL must start with sqlite: or postgres:".into(),
+            )),
+        }
+    }
 }
 
-pub async fn create_user(
-    pool: &SqlitePool,
-    email: &str,
-    name: &str,
-    password: &str,
-) -> Result<(), Error> {
-    query_file!("src/sql/create_user.sql", email, name, password)
-        .execute(pool)
-        .await?;
-    Ok(())
+/// the database must be migrated, see `migrate::run`
+pub async fn connect(database_url: &str) -> Result<Arc<dyn Repository>, Error> {
+    Ok(match Backend::from_url(database_url)? {
+        Backend::Sqlite => Arc::new(SqliteRepository::new(
+            SqlitePool::connect(database_url).await?,
+        )),
+        Backend::Postgres => Arc::new(PostgresRepository::new(
+            PgPool::connect(database_url).await?,
+        )),
+    })
 }
 
-#[derive(Serialize, Clone)]
+/// every query of the app, implemented once per database
+#[async_trait]
+pub trait Repository: Send + Sync {
+    async fn get_user_id_by_email(&self, email: &str) -> Result<Option<i64>, Error>;
+
+    async fn create_user(&self, email: &str, name: &str, password: &str) -> Result<(), Error>;
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<DbUser>, Error>;
+
+    /// replaces the entry if it is in the trash, its text is kept as an entry
+    /// revision. returns `None` if there is an entry which is not in the trash.
+    async fn create_entry(
+        &self,
+        user_id: i64,
+        date: Date,
+        text: EntryText,
+    ) -> Result<Option<i64>, Error>;
+
+    /// only updates the entry if it is still at `revision` (any revision if
+    /// `None`), returns the new revision if it was updated. the previous text is
+    /// kept as an entry revision, unless one was taken within `snapshot_interval`.
+    async fn update_entry_text_by_user_and_date(
+        &self,
+        text: EntryText,
+        user_id: i64,
+        date: Date,
+        revision: Option<i64>,
+        snapshot_interval: time::Duration,
+    ) -> Result<Option<i64>, Error>;
+
+    async fn list_entry_dates_by_user(&self, user_id: i64) -> Result<Vec<AppDate>, Error>;
+
+    async fn get_entry_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+    ) -> Result<Option<DbEntry>, Error>;
+
+    /// a page of entries in date order, for going through all entries without
+    /// loading them at once
+    async fn list_entries_by_user_after_date(
+        &self,
+        user_id: i64,
+        after: Option<Date>,
+        limit: i64,
+    ) -> Result<Vec<DbEntry>, Error>;
+
+    async fn list_entry_revisions_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+    ) -> Result<Vec<DbEntryRevisionSummary>, Error>;
+
+    async fn get_entry_revision_by_user_and_date_and_id(
+        &self,
+        user_id: i64,
+        date: Date,
+        id: i64,
+    ) -> Result<Option<DbEntryRevision>, Error>;
+
+    /// moves the entry to the trash, until it is restored or purged
+    async fn trash_entry_by_user_and_date(&self, user_id: i64, date: Date) -> Result<(), Error>;
+
+    async fn list_trashed_entries_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<DbTrashedEntry>, Error>;
+
+    /// returns false if the entry is not in the trash
+    async fn restore_entry_by_user_and_date(&self, user_id: i64, date: Date)
+    -> Result<bool, Error>;
+
+    /// only deletes the entry if it is in the trash
+    async fn purge_entry_by_user_and_date(&self, user_id: i64, date: Date) -> Result<(), Error>;
+
+    async fn purge_entries_by_user(&self, user_id: i64) -> Result<(), Error>;
+
+    /// returns the number of purged entries
+    async fn purge_entries_by_deleted_before(
+        &self,
+        retention: time::Duration,
+    ) -> Result<u64, Error>;
+
+    async fn get_user_by_id(&self, id: i64) -> Result<Option<DbUser>, Error>;
+
+    async fn create_session(
+        &self,
+        user_id: i64,
+        refresh_token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> Result<i64, Error>;
+
+    async fn get_session_by_id(&self, id: i64) -> Result<Option<DbSession>, Error>;
+
+    /// returns false if the session was revoked or rotated in the meantime
+    async fn rotate_session_refresh_token_by_id_and_hash(
+        &self,
+        new_refresh_token_hash: &str,
+        expires_at: OffsetDateTime,
+        id: i64,
+        old_refresh_token_hash: &str,
+    ) -> Result<bool, Error>;
+
+    async fn revoke_session_by_id(&self, id: i64) -> Result<(), Error>;
+
+    async fn revoke_sessions_by_user(&self, user_id: i64) -> Result<(), Error>;
+
+    async fn update_user_password_by_id(&self, password: &str, id: i64) -> Result<(), Error>;
+
+    async fn create_password_reset_token(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> Result<(), Error>;
+
+    async fn get_password_reset_token_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<DbPasswordResetToken>, Error>;
+
+    /// marks the token as used and sets the password of its user in one
+    /// transaction. returns false, changing nothing, if the token was already
+    /// used.
+    async fn use_password_reset_token_by_id(
+        &self,
+        id: i64,
+        user_id: i64,
+        password: &str,
+    ) -> Result<bool, Error>;
+
+    async fn delete_unused_password_reset_tokens_by_user(&self, user_id: i64) -> Result<(), Error>;
+
+    /// `None` keeps the current value
+    async fn update_user_by_id(
+        &self,
+        name: Option<&str>,
+        email: Option<&str>,
+        password: Option<&str>,
+        daily_word_goal: Option<i64>,
+        timezone: Option<&str>,
+        id: i64,
+    ) -> Result<(), Error>;
+
+    /// entries, sessions etc. are removed by `ON DELETE CASCADE`
+    async fn delete_user_by_id(&self, id: i64) -> Result<(), Error>;
+
+    async fn revoke_other_sessions_by_user(
+        &self,
+        user_id: i64,
+        current_session_id: i64,
+    ) -> Result<(), Error>;
+
+    async fn upsert_entry_search_text_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+        plain_text: &str,
+    ) -> Result<(), Error>;
+
+    async fn list_entries_without_search_text(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DbEntryWithoutSearchText>, Error>;
+
+    /// best matches first
+    async fn search_entries_by_user(
+        &self,
+        user_id: i64,
+        query: &SearchQuery,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DbEntrySearchResult>, Error>;
+
+    async fn get_rate_limit_bucket_by_key(&self, key: &str) -> Result<Option<BucketState>, Error>;
+
+    /// refills the bucket and consumes a token in one statement, so that
+    /// concurrent requests can't take the same token. unknown keys start with a
+    /// full bucket. returns false if no token was available.
+    async fn take_rate_limit_token_by_key(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+        now: f64,
+    ) -> Result<bool, Error>;
+
+    /// undoes `take_rate_limit_token_by_key`, never above the capacity
+    async fn return_rate_limit_token_by_key(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+        now: f64,
+    ) -> Result<(), Error>;
+
+    async fn delete_rate_limit_bucket_by_key(&self, key: &str) -> Result<(), Error>;
+
+    /// does nothing if totp is already enabled for the user
+    async fn upsert_pending_user_totp(&self, user_id: i64, secret: &str) -> Result<(), Error>;
+
+    async fn get_user_totp_by_user(&self, user_id: i64) -> Result<Option<DbUserTotp>, Error>;
+
+    /// returns false if totp was already enabled
+    async fn enable_user_totp_by_user(
+        &self,
+        last_used_step: i64,
+        user_id: i64,
+    ) -> Result<bool, Error>;
+
+    /// returns false if the same or a newer step was used in the meantime
+    async fn update_user_totp_last_used_step_by_user(
+        &self,
+        last_used_step: i64,
+        user_id: i64,
+    ) -> Result<bool, Error>;
+
+    /// also deletes the recovery codes, in one transaction
+    async fn delete_user_totp_by_user(&self, user_id: i64) -> Result<(), Error>;
+
+    /// invalidates all previous recovery codes of the user
+    async fn replace_recovery_codes_by_user(
+        &self,
+        user_id: i64,
+        code_hashes: &[String],
+    ) -> Result<(), Error>;
+
+    /// returns false if there is no unused recovery code with this hash
+    async fn use_recovery_code_by_user_and_hash(
+        &self,
+        user_id: i64,
+        code_hash: &str,
+    ) -> Result<bool, Error>;
+
+    /// the entries of every user, including trashed ones, ordered by and after
+    /// the `(user_id, date)` key, in pages of `limit`
+    async fn list_all_entries_after_key(
+        &self,
+        user_id: i64,
+        date: Date,
+        limit: i64,
+    ) -> Result<Vec<DbEntryWordCount>, Error>;
+
+    /// doesn't bump the revision, since the text is unchanged
+    async fn update_entry_word_count_by_user_and_date(
+        &self,
+        word_count: i64,
+        user_id: i64,
+        date: Date,
+    ) -> Result<(), Error>;
+
+    async fn list_entry_revisions_after_id(
+        &self,
+        id: i64,
+        limit: i64,
+    ) -> Result<Vec<DbEntryRevisionWordCount>, Error>;
+
+    async fn update_entry_revision_word_count_by_id(
+        &self,
+        word_count: i64,
+        id: i64,
+    ) -> Result<(), Error>;
+
+    /// over entries that are not in the trash
+    async fn get_entry_stats_by_user(&self, user_id: i64) -> Result<DbEntryStats, Error>;
+
+    /// the earliest one if several have the most words
+    async fn get_longest_entry_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Option<DbEntryDateWordCount>, Error>;
+
+    /// `key` is the day of the week, 0 for sunday
+    async fn list_weekday_stats_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<DbEntryHistogramBucket>, Error>;
+
+    /// `key` is the month, 1 for january
+    async fn list_month_stats_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<DbEntryHistogramBucket>, Error>;
+
+    /// `from` and `to` are inclusive
+    async fn list_entry_word_counts_by_user_and_date_range(
+        &self,
+        user_id: i64,
+        from: Date,
+        to: Date,
+    ) -> Result<Vec<DbEntryDateWordCount>, Error>;
+
+    /// dates of entries with at least `goal` words, in date order
+    async fn list_goal_met_entry_dates_by_user(
+        &self,
+        user_id: i64,
+        goal: i64,
+    ) -> Result<Vec<AppDate>, Error>;
+
+    /// entries from `from` to `to` (inclusive, unbounded if `None`) after the date
+    /// `after`, in date order
+    async fn list_entry_summaries_by_user_and_date_range(
+        &self,
+        user_id: i64,
+        from: Option<Date>,
+        to: Option<Date>,
+        after: Option<Date>,
+        preview_length: i64,
+        limit: i64,
+    ) -> Result<Vec<DbEntrySummary>, Error>;
+}
+
+#[derive(Serialize, Clone, FromRow)]
 pub struct DbUser {
     pub id: i64,
     pub email: String,
@@ -33,112 +373,211 @@ pub struct DbUser {
     #[serde(skip_serializing)]
     pub password: String,
     #[serde(rename = "createdAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
     pub created_at: AppDateTime,
+    #[serde(rename = "dailyWordGoal")]
+    pub daily_word_goal: i64,
+    pub timezone: String,
 }
 
-pub async fn get_user_by_email(pool: &SqlitePool, email: &str) -> Result<Option<DbUser>, Error> {
-    query_file_as!(DbUser, "src/sql/get_user_by_email.sql", email)
-        .fetch_optional(pool)
-        .await
+impl DbUser {
+    /// falls back to utc if the timezone is no longer known
+    pub fn today(&self) -> AppDate {
+        AppDate::today(timezones::get_by_name(&self.timezone).unwrap_or(timezones::db::UTC))
+    }
 }
 
-pub async fn check_entry_exists_by_user_and_date(
-    pool: &SqlitePool,
-    user_id: i64,
-    date: OffsetDateTime,
-) -> Result<Option<i64>, Error> {
-    let record = query_file!(
-        "src/sql/check_entry_exists_by_user_and_date.sql",
-        user_id,
-        date
-    )
-    .fetch_optional(pool)
-    .await?;
-    Ok(record.map(|r| r.user_id))
-}
-
-pub async fn create_entry(
-    pool: &SqlitePool,
-    user_id: i64,
-    date: OffsetDateTime,
-    text: Value,
-    word_count: i64
-) -> Result<(), Error> {
-    query_file!("src/sql/create_entry.sql", user_id, date, text, word_count)
-        .execute(pool)
-        .await?;
-    Ok(())
-}
-
-pub async fn update_entry_text_by_user_and_date(
-    pool: &SqlitePool,
-    text: Value,
-    word_count: i64,
-    user_id: i64,
-    date: OffsetDateTime,
-) -> Result<(), Error> {
-    query_file!(
-        "src/sql/update_entry_text_by_user_and_date.sql",
-        text,
-        word_count,
-        user_id,
-        date
-    )
-    .execute(pool)
-    .await?;
-    Ok(())
-}
-
-pub async fn list_entry_dates_by_user(
-    pool: &SqlitePool,
-    user_id: i64,
-) -> Result<Vec<AppDateTime>, Error> {
-    query_file!("src/sql/list_entry_dates_by_user.sql", user_id)
-        .fetch_all(pool)
-        .await
-        .map(|records| records.iter().map(|record| record.date.into()).collect())
+/// the text of an entry, with everything derived from it
+pub struct EntryText {
+    pub text: Value,
+    pub plain_text: String,
+    pub word_count: i64,
+}
+
+impl EntryText {
+    pub fn new(content: &TiptapJsonContent) -> Result<Self, serde_json::Error> {
+        Ok(EntryText {
+            text: serde_json::to_value(content)?,
+            plain_text: content.to_plain_text(),
+            word_count: content.count_words(),
+        })
+    }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, FromRow)]
 pub struct DbEntry {
     #[serde(rename = "userId")]
     user_id: i64,
-    #[serde(serialize_with = "crate::datetime::AppDateTime::serialize_to_yyyy_mm_dd_string")]
-    date: AppDateTime,
-    text: Value,
+    #[sqlx(try_from = "Date")]
+    pub date: AppDate,
+    pub text: Value,
+    pub word_count: i64,
+    pub revision: i64,
+    #[serde(rename = "createdAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
+    pub created_at: AppDateTime,
+    #[serde(rename = "updatedAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
+    pub updated_at: AppDateTime,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct DbEntryRevisionSummary {
+    id: i64,
+    revision: i64,
     word_count: i64,
+    #[serde(rename = "savedAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
+    saved_at: AppDateTime,
     #[serde(rename = "createdAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
     created_at: AppDateTime,
 }
 
-pub async fn get_entry_by_user_and_date(
-    pool: &SqlitePool,
-    user_id: i64,
-    date: OffsetDateTime,
-) -> Result<Option<DbEntry>, Error> {
-    query_file_as!(
-        DbEntry,
-        "src/sql/get_entry_by_user_and_date.sql",
-        user_id,
-        date
-    )
-    .fetch_optional(pool)
-    .await
-}
-
-pub async fn delete_entry_by_user_and_date(
-    pool: &SqlitePool,
-    user_id: i64,
-    date: OffsetDateTime,
-) -> Result<(), Error> {
-    query_file!("src/sql/delete_entry_by_user_and_date.sql", user_id, date)
-        .execute(pool)
-        .await?;
-    Ok(())
-}
-
-pub async fn get_user_by_id(pool: &SqlitePool, id: i64) -> Result<Option<DbUser>, Error> {
-    query_file_as!(DbUser, "src/sql/get_user_by_id.sql", id)
-        .fetch_optional(pool)
-        .await
+#[derive(Serialize, FromRow)]
+pub struct DbEntryRevision {
+    pub id: i64,
+    revision: i64,
+    pub text: Value,
+    word_count: i64,
+    #[serde(rename = "savedAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
+    saved_at: AppDateTime,
+    #[serde(rename = "createdAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
+    created_at: AppDateTime,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct DbTrashedEntry {
+    #[sqlx(try_from = "Date")]
+    date: AppDate,
+    word_count: i64,
+    #[serde(rename = "deletedAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
+    deleted_at: AppDateTime,
+}
+
+#[derive(Clone, FromRow)]
+pub struct DbSession {
+    pub id: i64,
+    pub user_id: i64,
+    pub refresh_token_hash: String,
+    pub expires_at: OffsetDateTime,
+    pub revoked_at: Option<OffsetDateTime>,
+}
+
+impl DbSession {
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at > OffsetDateTime::now_utc()
+    }
+}
+
+#[derive(FromRow)]
+pub struct DbPasswordResetToken {
+    pub id: i64,
+    pub user_id: i64,
+    pub expires_at: OffsetDateTime,
+    pub used_at: Option<OffsetDateTime>,
+}
+
+impl DbPasswordResetToken {
+    pub fn is_usable(&self) -> bool {
+        self.used_at.is_none() && self.expires_at > OffsetDateTime::now_utc()
+    }
+}
+
+#[derive(FromRow)]
+pub struct DbEntryWithoutSearchText {
+    pub user_id: i64,
+    pub date: Date,
+    pub text: Value,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct DbEntrySearchResult {
+    #[sqlx(try_from = "Date")]
+    date: AppDate,
+    pub snippet: String,
+    /// lower is better
+    rank: f64,
+}
+
+/// e.g. unbalanced parentheses in the search query
+pub fn is_fts_syntax_error(err: &Error) -> bool {
+    err.as_database_error().is_some_and(|err| {
+        err.message().starts_with("fts5: syntax error")
+            || err.message().starts_with("syntax error in tsquery")
+    })
+}
+
+pub fn is_unique_violation(err: &Error) -> bool {
+    err.as_database_error()
+        .is_some_and(|err| err.is_unique_violation())
+}
+
+#[derive(FromRow)]
+pub struct DbUserTotp {
+    pub secret: String,
+    pub enabled_at: Option<OffsetDateTime>,
+    pub last_used_step: Option<i64>,
+}
+
+impl DbUserTotp {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
+
+#[derive(FromRow)]
+pub struct DbEntryWordCount {
+    pub user_id: i64,
+    pub date: Date,
+    pub text: Value,
+    pub word_count: i64,
+}
+
+#[derive(FromRow)]
+pub struct DbEntryRevisionWordCount {
+    pub id: i64,
+    pub text: Value,
+    pub word_count: i64,
+}
+
+#[derive(FromRow)]
+pub struct DbEntryStats {
+    pub entries: i64,
+    pub words: i64,
+    pub average_words: f64,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct DbEntryDateWordCount {
+    #[sqlx(try_from = "Date")]
+    pub date: AppDate,
+    pub word_count: i64,
+}
+
+/// entries and words grouped by `key`
+#[derive(FromRow)]
+pub struct DbEntryHistogramBucket {
+    pub key: i64,
+    pub entries: i64,
+    pub words: i64,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct DbEntrySummary {
+    #[sqlx(try_from = "Date")]
+    pub date: AppDate,
+    word_count: i64,
+    #[serde(rename = "createdAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
+    created_at: AppDateTime,
+    #[serde(rename = "updatedAt")]
+    #[sqlx(try_from = "OffsetDateTime")]
+    updated_at: AppDateTime,
+    /// the first `preview_length` characters of the plain text, empty until
+    /// the entry is indexed, see `search::spawn_index_task`
+    pub preview: String,
 }
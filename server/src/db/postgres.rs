@@ -0,0 +1,759 @@
+// queries are checked at compile time against the sqlite database only, so
+// these are plain `sqlx::query` calls, covered by the tests in `db::tests`
+
+use async_trait::async_trait;
+use sqlx::{Error, PgPool, query, query_as, query_scalar};
+use time::{Date, OffsetDateTime};
+
+use super::{
+    DbEntry, DbEntryDateWordCount, DbEntryHistogramBucket, DbEntryRevision, DbEntryRevisionSummary,
+    DbEntryRevisionWordCount, DbEntrySearchResult, DbEntryStats, DbEntrySummary,
+    DbEntryWithoutSearchText, DbEntryWordCount, DbPasswordResetToken, DbSession, DbTrashedEntry,
+    DbUser, DbUserTotp, EntryText, Repository,
+};
+use crate::{
+    datetime::AppDate,
+    rate_limit::{BucketState, TokenBucket},
+    search::{self, SearchQuery},
+};
+
+macro_rules! sql {
+    ($name:literal) => {
+        include_str!(concat!("../sql/postgres/", $name, ".sql"))
+    };
+}
+
+pub struct PostgresRepository {
+    pool: PgPool,
+}
+
+impl PostgresRepository {
+    pub fn new(pool: PgPool) -> Self {
+        PostgresRepository { pool }
+    }
+}
+
+/// like the snippet of the sqlite fts table
+/// ref: https://www.postgresql.org/docs/current/textsearch-controls.html#TEXTSEARCH-HEADLINE
+fn headline_options() -> String {
+    format!(
+        "StartSel=\"{}\", StopSel=\"{}\", MaxWords={}, MinWords={}, MaxFragments=1, FragmentDelimiter=\"{}\"",
+        search::HIGHLIGHT_START,
+        search::HIGHLIGHT_END,
+        search::SNIPPET_TOKENS,
+        search::SNIPPET_TOKENS / 2,
+        search::SNIPPET_ELLIPSIS,
+    )
+}
+
+#[async_trait]
+impl Repository for PostgresRepository {
+    async fn get_user_id_by_email(&self, email: &str) -> Result<Option<i64>, Error> {
+        query_scalar(sql!("get_user_id_by_email"))
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn create_user(&self, email: &str, name: &str, password: &str) -> Result<(), Error> {
+        query(sql!("create_user"))
+            .bind(email)
+            .bind(name)
+            .bind(password)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<DbUser>, Error> {
+        query_as(sql!("get_user_by_email"))
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn create_entry(
+        &self,
+        user_id: i64,
+        date: Date,
+        text: EntryText,
+    ) -> Result<Option<i64>, Error> {
+        let mut tx = self.pool.begin().await?;
+        query(sql!("create_entry_revision_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .bind(0.0)
+            .execute(&mut *tx)
+            .await?;
+
+        let revision: Option<i64> = query_scalar(sql!("create_entry"))
+            .bind(user_id)
+            .bind(date)
+            .bind(text.text)
+            .bind(text.word_count)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let Some(revision) = revision else {
+            return Ok(None);
+        };
+        query(sql!("upsert_entry_search_text_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .bind(text.plain_text)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(revision))
+    }
+
+    async fn update_entry_text_by_user_and_date(
+        &self,
+        text: EntryText,
+        user_id: i64,
+        date: Date,
+        revision: Option<i64>,
+        snapshot_interval: time::Duration,
+    ) -> Result<Option<i64>, Error> {
+        let mut tx = self.pool.begin().await?;
+        query(sql!("create_entry_revision_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .bind(snapshot_interval.as_seconds_f64())
+            .execute(&mut *tx)
+            .await?;
+
+        let revision: Option<i64> = query_scalar(sql!("update_entry_text_by_user_and_date"))
+            .bind(text.text)
+            .bind(text.word_count)
+            .bind(user_id)
+            .bind(date)
+            .bind(revision)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        // dropping the transaction rolls back the snapshot
+        let Some(revision) = revision else {
+            return Ok(None);
+        };
+        query(sql!("upsert_entry_search_text_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .bind(text.plain_text)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(revision))
+    }
+
+    async fn list_entry_dates_by_user(&self, user_id: i64) -> Result<Vec<AppDate>, Error> {
+        query_scalar(sql!("list_entry_dates_by_user"))
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+            .map(|dates| dates.into_iter().map(AppDate).collect())
+    }
+
+    async fn get_entry_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+    ) -> Result<Option<DbEntry>, Error> {
+        query_as(sql!("get_entry_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn list_entries_by_user_after_date(
+        &self,
+        user_id: i64,
+        after: Option<Date>,
+        limit: i64,
+    ) -> Result<Vec<DbEntry>, Error> {
+        query_as(sql!("list_entries_by_user_after_date"))
+            .bind(user_id)
+            .bind(after)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn list_entry_revisions_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+    ) -> Result<Vec<DbEntryRevisionSummary>, Error> {
+        query_as(sql!("list_entry_revisions_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn get_entry_revision_by_user_and_date_and_id(
+        &self,
+        user_id: i64,
+        date: Date,
+        id: i64,
+    ) -> Result<Option<DbEntryRevision>, Error> {
+        query_as(sql!("get_entry_revision_by_user_and_date_and_id"))
+            .bind(user_id)
+            .bind(date)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn trash_entry_by_user_and_date(&self, user_id: i64, date: Date) -> Result<(), Error> {
+        query(sql!("trash_entry_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_trashed_entries_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<DbTrashedEntry>, Error> {
+        query_as(sql!("list_trashed_entries_by_user"))
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn restore_entry_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+    ) -> Result<bool, Error> {
+        let result = query(sql!("restore_entry_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn purge_entry_by_user_and_date(&self, user_id: i64, date: Date) -> Result<(), Error> {
+        query(sql!("purge_entry_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn purge_entries_by_user(&self, user_id: i64) -> Result<(), Error> {
+        query(sql!("purge_entries_by_user"))
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn purge_entries_by_deleted_before(
+        &self,
+        retention: time::Duration,
+    ) -> Result<u64, Error> {
+        let result = query(sql!("purge_entries_by_deleted_before"))
+            .bind(retention.as_seconds_f64())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_user_by_id(&self, id: i64) -> Result<Option<DbUser>, Error> {
+        query_as(sql!("get_user_by_id"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn create_session(
+        &self,
+        user_id: i64,
+        refresh_token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> Result<i64, Error> {
+        query_scalar(sql!("create_session"))
+            .bind(user_id)
+            .bind(refresh_token_hash)
+            .bind(expires_at)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn get_session_by_id(&self, id: i64) -> Result<Option<DbSession>, Error> {
+        query_as(sql!("get_session_by_id"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn rotate_session_refresh_token_by_id_and_hash(
+        &self,
+        new_refresh_token_hash: &str,
+        expires_at: OffsetDateTime,
+        id: i64,
+        old_refresh_token_hash: &str,
+    ) -> Result<bool, Error> {
+        let result = query(sql!("rotate_session_refresh_token_by_id_and_hash"))
+            .bind(new_refresh_token_hash)
+            .bind(expires_at)
+            .bind(id)
+            .bind(old_refresh_token_hash)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn revoke_session_by_id(&self, id: i64) -> Result<(), Error> {
+        query(sql!("revoke_session_by_id"))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke_sessions_by_user(&self, user_id: i64) -> Result<(), Error> {
+        query(sql!("revoke_sessions_by_user"))
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_user_password_by_id(&self, password: &str, id: i64) -> Result<(), Error> {
+        query(sql!("update_user_password_by_id"))
+            .bind(password)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn create_password_reset_token(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> Result<(), Error> {
+        query(sql!("create_password_reset_token"))
+            .bind(user_id)
+            .bind(token_hash)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_password_reset_token_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<DbPasswordResetToken>, Error> {
+        query_as(sql!("get_password_reset_token_by_hash"))
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn use_password_reset_token_by_id(
+        &self,
+        id: i64,
+        user_id: i64,
+        password: &str,
+    ) -> Result<bool, Error> {
+        let mut tx = self.pool.begin().await?;
+        let result = query(sql!("use_password_reset_token_by_id"))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() != 1 {
+            return Ok(false);
+        }
+        query(sql!("update_user_password_by_id"))
+            .bind(password)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn delete_unused_password_reset_tokens_by_user(&self, user_id: i64) -> Result<(), Error> {
+        query(sql!("delete_unused_password_reset_tokens_by_user"))
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_user_by_id(
+        &self,
+        name: Option<&str>,
+        email: Option<&str>,
+        password: Option<&str>,
+        daily_word_goal: Option<i64>,
+        timezone: Option<&str>,
+        id: i64,
+    ) -> Result<(), Error> {
+        query(sql!("update_user_by_id"))
+            .bind(name)
+            .bind(email)
+            .bind(password)
+            .bind(daily_word_goal)
+            .bind(timezone)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_user_by_id(&self, id: i64) -> Result<(), Error> {
+        query(sql!("delete_user_by_id"))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke_other_sessions_by_user(
+        &self,
+        user_id: i64,
+        current_session_id: i64,
+    ) -> Result<(), Error> {
+        query(sql!("revoke_other_sessions_by_user"))
+            .bind(user_id)
+            .bind(current_session_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_entry_search_text_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+        plain_text: &str,
+    ) -> Result<(), Error> {
+        query(sql!("upsert_entry_search_text_by_user_and_date"))
+            .bind(user_id)
+            .bind(date)
+            .bind(plain_text)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_entries_without_search_text(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DbEntryWithoutSearchText>, Error> {
+        query_as(sql!("list_entries_without_search_text"))
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn search_entries_by_user(
+        &self,
+        user_id: i64,
+        query: &SearchQuery,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DbEntrySearchResult>, Error> {
+        query_as(sql!("search_entries_by_user"))
+            .bind(headline_options())
+            .bind(query.to_tsquery())
+            .bind(user_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn get_rate_limit_bucket_by_key(&self, key: &str) -> Result<Option<BucketState>, Error> {
+        query_as(sql!("get_rate_limit_bucket_by_key"))
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn take_rate_limit_token_by_key(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+        now: f64,
+    ) -> Result<bool, Error> {
+        query(sql!("create_rate_limit_bucket"))
+            .bind(key)
+            .bind(bucket.capacity)
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        // the row lock makes concurrent updates recheck the condition
+        let result = query(sql!("take_rate_limit_token_by_key"))
+            .bind(bucket.capacity)
+            .bind(now)
+            .bind(bucket.refill_rate)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn return_rate_limit_token_by_key(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+        now: f64,
+    ) -> Result<(), Error> {
+        query(sql!("return_rate_limit_token_by_key"))
+            .bind(bucket.capacity)
+            .bind(now)
+            .bind(bucket.refill_rate)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_rate_limit_bucket_by_key(&self, key: &str) -> Result<(), Error> {
+        query(sql!("delete_rate_limit_bucket_by_key"))
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_pending_user_totp(&self, user_id: i64, secret: &str) -> Result<(), Error> {
+        query(sql!("upsert_pending_user_totp"))
+            .bind(user_id)
+            .bind(secret)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_user_totp_by_user(&self, user_id: i64) -> Result<Option<DbUserTotp>, Error> {
+        query_as(sql!("get_user_totp_by_user"))
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn enable_user_totp_by_user(
+        &self,
+        last_used_step: i64,
+        user_id: i64,
+    ) -> Result<bool, Error> {
+        let result = query(sql!("enable_user_totp_by_user"))
+            .bind(last_used_step)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn update_user_totp_last_used_step_by_user(
+        &self,
+        last_used_step: i64,
+        user_id: i64,
+    ) -> Result<bool, Error> {
+        let result = query(sql!("update_user_totp_last_used_step_by_user"))
+            .bind(last_used_step)
+            .bind(user_id)
+            .bind(last_used_step)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_user_totp_by_user(&self, user_id: i64) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        query(sql!("delete_user_totp_by_user"))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        query(sql!("delete_recovery_codes_by_user"))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+
+    async fn replace_recovery_codes_by_user(
+        &self,
+        user_id: i64,
+        code_hashes: &[String],
+    ) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        query(sql!("delete_recovery_codes_by_user"))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        for code_hash in code_hashes {
+            query(sql!("create_recovery_code"))
+                .bind(user_id)
+                .bind(code_hash)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    async fn use_recovery_code_by_user_and_hash(
+        &self,
+        user_id: i64,
+        code_hash: &str,
+    ) -> Result<bool, Error> {
+        let result = query(sql!("use_recovery_code_by_user_and_hash"))
+            .bind(user_id)
+            .bind(code_hash)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn list_all_entries_after_key(
+        &self,
+        user_id: i64,
+        date: Date,
+        limit: i64,
+    ) -> Result<Vec<DbEntryWordCount>, Error> {
+        query_as(sql!("list_all_entries_after_key"))
+            .bind(user_id)
+            .bind(date)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn update_entry_word_count_by_user_and_date(
+        &self,
+        word_count: i64,
+        user_id: i64,
+        date: Date,
+    ) -> Result<(), Error> {
+        query(sql!("update_entry_word_count_by_user_and_date"))
+            .bind(word_count)
+            .bind(user_id)
+            .bind(date)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_entry_revisions_after_id(
+        &self,
+        id: i64,
+        limit: i64,
+    ) -> Result<Vec<DbEntryRevisionWordCount>, Error> {
+        query_as(sql!("list_entry_revisions_after_id"))
+            .bind(id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn update_entry_revision_word_count_by_id(
+        &self,
+        word_count: i64,
+        id: i64,
+    ) -> Result<(), Error> {
+        query(sql!("update_entry_revision_word_count_by_id"))
+            .bind(word_count)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_entry_stats_by_user(&self, user_id: i64) -> Result<DbEntryStats, Error> {
+        query_as(sql!("get_entry_stats_by_user"))
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn get_longest_entry_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Option<DbEntryDateWordCount>, Error> {
+        query_as(sql!("get_longest_entry_by_user"))
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn list_weekday_stats_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<DbEntryHistogramBucket>, Error> {
+        query_as(sql!("list_weekday_stats_by_user"))
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn list_month_stats_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<DbEntryHistogramBucket>, Error> {
+        query_as(sql!("list_month_stats_by_user"))
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn list_entry_word_counts_by_user_and_date_range(
+        &self,
+        user_id: i64,
+        from: Date,
+        to: Date,
+    ) -> Result<Vec<DbEntryDateWordCount>, Error> {
+        query_as(sql!("list_entry_word_counts_by_user_and_date_range"))
+            .bind(user_id)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn list_goal_met_entry_dates_by_user(
+        &self,
+        user_id: i64,
+        goal: i64,
+    ) -> Result<Vec<AppDate>, Error> {
+        query_scalar(sql!("list_goal_met_entry_dates_by_user"))
+            .bind(user_id)
+            .bind(goal)
+            .fetch_all(&self.pool)
+            .await
+            .map(|dates| dates.into_iter().map(AppDate).collect())
+    }
+
+    async fn list_entry_summaries_by_user_and_date_range(
+        &self,
+        user_id: i64,
+        from: Option<Date>,
+        to: Option<Date>,
+        after: Option<Date>,
+        preview_length: i64,
+        limit: i64,
+    ) -> Result<Vec<DbEntrySummary>, Error> {
+        query_as(sql!("list_entry_summaries_by_user_and_date_range"))
+            .bind(preview_length)
+            .bind(user_id)
+            .bind(from)
+            .bind(to)
+            .bind(after)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+}
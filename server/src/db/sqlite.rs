@@ -0,0 +1,805 @@
+use async_trait::async_trait;
+use sqlx::{Error, SqlitePool, query_file, query_file_as};
+use time::{Date, OffsetDateTime};
+
+use super::{
+    DbEntry, DbEntryDateWordCount, DbEntryHistogramBucket, DbEntryRevision, DbEntryRevisionSummary,
+    DbEntryRevisionWordCount, DbEntrySearchResult, DbEntryStats, DbEntrySummary,
+    DbEntryWithoutSearchText, DbEntryWordCount, DbPasswordResetToken, DbSession, DbTrashedEntry,
+    DbUser, DbUserTotp, EntryText, Repository,
+};
+use crate::{
+    datetime::AppDate,
+    rate_limit::{BucketState, TokenBucket},
+    search::{self, SearchQuery},
+};
+
+pub struct SqliteRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        SqliteRepository { pool }
+    }
+}
+
+#[async_trait]
+impl Repository for SqliteRepository {
+    async fn get_user_id_by_email(&self, email: &str) -> Result<Option<i64>, Error> {
+        let record = query_file!("src/sql/get_user_id_by_email.sql", email)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(record.map(|r| r.id))
+    }
+
+    async fn create_user(&self, email: &str, name: &str, password: &str) -> Result<(), Error> {
+        query_file!("src/sql/create_user.sql", email, name, password)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<DbUser>, Error> {
+        query_file_as!(DbUser, "src/sql/get_user_by_email.sql", email)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn create_entry(
+        &self,
+        user_id: i64,
+        date: Date,
+        text: EntryText,
+    ) -> Result<Option<i64>, Error> {
+        let mut tx = self.pool.begin().await?;
+        query_file!(
+            "src/sql/create_entry_revision_by_user_and_date.sql",
+            user_id,
+            date,
+            "-0 seconds"
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let record = query_file!(
+            "src/sql/create_entry.sql",
+            user_id,
+            date,
+            text.text,
+            text.word_count
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(record) = record else {
+            return Ok(None);
+        };
+        query_file!(
+            "src/sql/upsert_entry_search_text_by_user_and_date.sql",
+            user_id,
+            date,
+            text.plain_text
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(record.revision))
+    }
+
+    async fn update_entry_text_by_user_and_date(
+        &self,
+        text: EntryText,
+        user_id: i64,
+        date: Date,
+        revision: Option<i64>,
+        snapshot_interval: time::Duration,
+    ) -> Result<Option<i64>, Error> {
+        let mut tx = self.pool.begin().await?;
+        // ref: https://www.sqlite.org/lang_datefunc.html#modifiers
+        let modifier = format!("-{} seconds", snapshot_interval.whole_seconds());
+        query_file!(
+            "src/sql/create_entry_revision_by_user_and_date.sql",
+            user_id,
+            date,
+            modifier
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let record = query_file!(
+            "src/sql/update_entry_text_by_user_and_date.sql",
+            text.text,
+            text.word_count,
+            user_id,
+            date,
+            revision
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        // dropping the transaction rolls back the snapshot
+        let Some(record) = record else {
+            return Ok(None);
+        };
+        query_file!(
+            "src/sql/upsert_entry_search_text_by_user_and_date.sql",
+            user_id,
+            date,
+            text.plain_text
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(record.revision))
+    }
+
+    async fn list_entry_dates_by_user(&self, user_id: i64) -> Result<Vec<AppDate>, Error> {
+        query_file!("src/sql/list_entry_dates_by_user.sql", user_id)
+            .fetch_all(&self.pool)
+            .await
+            .map(|records| records.iter().map(|record| record.date.into()).collect())
+    }
+
+    async fn get_entry_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+    ) -> Result<Option<DbEntry>, Error> {
+        query_file_as!(
+            DbEntry,
+            "src/sql/get_entry_by_user_and_date.sql",
+            user_id,
+            date
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn list_entries_by_user_after_date(
+        &self,
+        user_id: i64,
+        after: Option<Date>,
+        limit: i64,
+    ) -> Result<Vec<DbEntry>, Error> {
+        query_file_as!(
+            DbEntry,
+            "src/sql/list_entries_by_user_after_date.sql",
+            user_id,
+            after,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_entry_revisions_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+    ) -> Result<Vec<DbEntryRevisionSummary>, Error> {
+        query_file_as!(
+            DbEntryRevisionSummary,
+            "src/sql/list_entry_revisions_by_user_and_date.sql",
+            user_id,
+            date
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_entry_revision_by_user_and_date_and_id(
+        &self,
+        user_id: i64,
+        date: Date,
+        id: i64,
+    ) -> Result<Option<DbEntryRevision>, Error> {
+        query_file_as!(
+            DbEntryRevision,
+            "src/sql/get_entry_revision_by_user_and_date_and_id.sql",
+            user_id,
+            date,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn trash_entry_by_user_and_date(&self, user_id: i64, date: Date) -> Result<(), Error> {
+        query_file!("src/sql/trash_entry_by_user_and_date.sql", user_id, date)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_trashed_entries_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<DbTrashedEntry>, Error> {
+        query_file_as!(
+            DbTrashedEntry,
+            "src/sql/list_trashed_entries_by_user.sql",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn restore_entry_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+    ) -> Result<bool, Error> {
+        let result = query_file!("src/sql/restore_entry_by_user_and_date.sql", user_id, date)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn purge_entry_by_user_and_date(&self, user_id: i64, date: Date) -> Result<(), Error> {
+        query_file!("src/sql/purge_entry_by_user_and_date.sql", user_id, date)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn purge_entries_by_user(&self, user_id: i64) -> Result<(), Error> {
+        query_file!("src/sql/purge_entries_by_user.sql", user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn purge_entries_by_deleted_before(
+        &self,
+        retention: time::Duration,
+    ) -> Result<u64, Error> {
+        let modifier = format!("-{} seconds", retention.whole_seconds());
+        let result = query_file!("src/sql/purge_entries_by_deleted_before.sql", modifier)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn get_user_by_id(&self, id: i64) -> Result<Option<DbUser>, Error> {
+        query_file_as!(DbUser, "src/sql/get_user_by_id.sql", id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn create_session(
+        &self,
+        user_id: i64,
+        refresh_token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> Result<i64, Error> {
+        let record = query_file!(
+            "src/sql/create_session.sql",
+            user_id,
+            refresh_token_hash,
+            expires_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(record.id)
+    }
+
+    async fn get_session_by_id(&self, id: i64) -> Result<Option<DbSession>, Error> {
+        query_file_as!(DbSession, "src/sql/get_session_by_id.sql", id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn rotate_session_refresh_token_by_id_and_hash(
+        &self,
+        new_refresh_token_hash: &str,
+        expires_at: OffsetDateTime,
+        id: i64,
+        old_refresh_token_hash: &str,
+    ) -> Result<bool, Error> {
+        let result = query_file!(
+            "src/sql/rotate_session_refresh_token_by_id_and_hash.sql",
+            new_refresh_token_hash,
+            expires_at,
+            id,
+            old_refresh_token_hash
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn revoke_session_by_id(&self, id: i64) -> Result<(), Error> {
+        query_file!("src/sql/revoke_session_by_id.sql", id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke_sessions_by_user(&self, user_id: i64) -> Result<(), Error> {
+        query_file!("src/sql/revoke_sessions_by_user.sql", user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_user_password_by_id(&self, password: &str, id: i64) -> Result<(), Error> {
+        query_file!("src/sql/update_user_password_by_id.sql", password, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn create_password_reset_token(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> Result<(), Error> {
+        query_file!(
+            "src/sql/create_password_reset_token.sql",
+            user_id,
+            token_hash,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_password_reset_token_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<DbPasswordResetToken>, Error> {
+        query_file_as!(
+            DbPasswordResetToken,
+            "src/sql/get_password_reset_token_by_hash.sql",
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn use_password_reset_token_by_id(
+        &self,
+        id: i64,
+        user_id: i64,
+        password: &str,
+    ) -> Result<bool, Error> {
+        let mut tx = self.pool.begin().await?;
+        let result = query_file!("src/sql/use_password_reset_token_by_id.sql", id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() != 1 {
+            return Ok(false);
+        }
+        query_file!("src/sql/update_user_password_by_id.sql", password, user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn delete_unused_password_reset_tokens_by_user(&self, user_id: i64) -> Result<(), Error> {
+        query_file!(
+            "src/sql/delete_unused_password_reset_tokens_by_user.sql",
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn update_user_by_id(
+        &self,
+        name: Option<&str>,
+        email: Option<&str>,
+        password: Option<&str>,
+        daily_word_goal: Option<i64>,
+        timezone: Option<&str>,
+        id: i64,
+    ) -> Result<(), Error> {
+        query_file!(
+            "src/sql/update_user_by_id.sql",
+            name,
+            email,
+            password,
+            daily_word_goal,
+            timezone,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_user_by_id(&self, id: i64) -> Result<(), Error> {
+        query_file!("src/sql/delete_user_by_id.sql", id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke_other_sessions_by_user(
+        &self,
+        user_id: i64,
+        current_session_id: i64,
+    ) -> Result<(), Error> {
+        query_file!(
+            "src/sql/revoke_other_sessions_by_user.sql",
+            user_id,
+            current_session_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_entry_search_text_by_user_and_date(
+        &self,
+        user_id: i64,
+        date: Date,
+        plain_text: &str,
+    ) -> Result<(), Error> {
+        query_file!(
+            "src/sql/upsert_entry_search_text_by_user_and_date.sql",
+            user_id,
+            date,
+            plain_text
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_entries_without_search_text(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DbEntryWithoutSearchText>, Error> {
+        query_file_as!(
+            DbEntryWithoutSearchText,
+            "src/sql/list_entries_without_search_text.sql",
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn search_entries_by_user(
+        &self,
+        user_id: i64,
+        query: &SearchQuery,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DbEntrySearchResult>, Error> {
+        let query = query.to_fts_query();
+        query_file_as!(
+            DbEntrySearchResult,
+            "src/sql/search_entries_by_user.sql",
+            search::HIGHLIGHT_START,
+            search::HIGHLIGHT_END,
+            search::SNIPPET_ELLIPSIS,
+            search::SNIPPET_TOKENS,
+            query,
+            user_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_rate_limit_bucket_by_key(&self, key: &str) -> Result<Option<BucketState>, Error> {
+        query_file_as!(BucketState, "src/sql/get_rate_limit_bucket_by_key.sql", key)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn take_rate_limit_token_by_key(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+        now: f64,
+    ) -> Result<bool, Error> {
+        query_file!(
+            "src/sql/create_rate_limit_bucket.sql",
+            key,
+            bucket.capacity,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+        // the refill is the same as in `TokenBucket::take`
+        let result = query_file!(
+            "src/sql/take_rate_limit_token_by_key.sql",
+            bucket.capacity,
+            now,
+            bucket.refill_rate,
+            now,
+            key,
+            bucket.capacity,
+            now,
+            bucket.refill_rate
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn return_rate_limit_token_by_key(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+        now: f64,
+    ) -> Result<(), Error> {
+        query_file!(
+            "src/sql/return_rate_limit_token_by_key.sql",
+            bucket.capacity,
+            now,
+            bucket.refill_rate,
+            now,
+            key
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_rate_limit_bucket_by_key(&self, key: &str) -> Result<(), Error> {
+        query_file!("src/sql/delete_rate_limit_bucket_by_key.sql", key)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_pending_user_totp(&self, user_id: i64, secret: &str) -> Result<(), Error> {
+        query_file!("src/sql/upsert_pending_user_totp.sql", user_id, secret)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_user_totp_by_user(&self, user_id: i64) -> Result<Option<DbUserTotp>, Error> {
+        query_file_as!(DbUserTotp, "src/sql/get_user_totp_by_user.sql", user_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn enable_user_totp_by_user(
+        &self,
+        last_used_step: i64,
+        user_id: i64,
+    ) -> Result<bool, Error> {
+        let result = query_file!(
+            "src/sql/enable_user_totp_by_user.sql",
+            last_used_step,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn update_user_totp_last_used_step_by_user(
+        &self,
+        last_used_step: i64,
+        user_id: i64,
+    ) -> Result<bool, Error> {
+        let result = query_file!(
+            "src/sql/update_user_totp_last_used_step_by_user.sql",
+            last_used_step,
+            user_id,
+            last_used_step
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_user_totp_by_user(&self, user_id: i64) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        query_file!("src/sql/delete_user_totp_by_user.sql", user_id)
+            .execute(&mut *tx)
+            .await?;
+        query_file!("src/sql/delete_recovery_codes_by_user.sql", user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+
+    async fn replace_recovery_codes_by_user(
+        &self,
+        user_id: i64,
+        code_hashes: &[String],
+    ) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        query_file!("src/sql/delete_recovery_codes_by_user.sql", user_id)
+            .execute(&mut *tx)
+            .await?;
+        for code_hash in code_hashes {
+            query_file!("src/sql/create_recovery_code.sql", user_id, code_hash)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    async fn use_recovery_code_by_user_and_hash(
+        &self,
+        user_id: i64,
+        code_hash: &str,
+    ) -> Result<bool, Error> {
+        let result = query_file!(
+            "src/sql/use_recovery_code_by_user_and_hash.sql",
+            user_id,
+            code_hash
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn list_all_entries_after_key(
+        &self,
+        user_id: i64,
+        date: Date,
+        limit: i64,
+    ) -> Result<Vec<DbEntryWordCount>, Error> {
+        query_file_as!(
+            DbEntryWordCount,
+            "src/sql/list_all_entries_after_key.sql",
+            user_id,
+            date,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn update_entry_word_count_by_user_and_date(
+        &self,
+        word_count: i64,
+        user_id: i64,
+        date: Date,
+    ) -> Result<(), Error> {
+        query_file!(
+            "src/sql/update_entry_word_count_by_user_and_date.sql",
+            word_count,
+            user_id,
+            date
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_entry_revisions_after_id(
+        &self,
+        id: i64,
+        limit: i64,
+    ) -> Result<Vec<DbEntryRevisionWordCount>, Error> {
+        query_file_as!(
+            DbEntryRevisionWordCount,
+            "src/sql/list_entry_revisions_after_id.sql",
+            id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn update_entry_revision_word_count_by_id(
+        &self,
+        word_count: i64,
+        id: i64,
+    ) -> Result<(), Error> {
+        query_file!(
+            "src/sql/update_entry_revision_word_count_by_id.sql",
+            word_count,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_entry_stats_by_user(&self, user_id: i64) -> Result<DbEntryStats, Error> {
+        query_file_as!(DbEntryStats, "src/sql/get_entry_stats_by_user.sql", user_id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn get_longest_entry_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Option<DbEntryDateWordCount>, Error> {
+        query_file_as!(
+            DbEntryDateWordCount,
+            "src/sql/get_longest_entry_by_user.sql",
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn list_weekday_stats_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<DbEntryHistogramBucket>, Error> {
+        query_file_as!(
+            DbEntryHistogramBucket,
+            "src/sql/list_weekday_stats_by_user.sql",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_month_stats_by_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<DbEntryHistogramBucket>, Error> {
+        query_file_as!(
+            DbEntryHistogramBucket,
+            "src/sql/list_month_stats_by_user.sql",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_entry_word_counts_by_user_and_date_range(
+        &self,
+        user_id: i64,
+        from: Date,
+        to: Date,
+    ) -> Result<Vec<DbEntryDateWordCount>, Error> {
+        query_file_as!(
+            DbEntryDateWordCount,
+            "src/sql/list_entry_word_counts_by_user_and_date_range.sql",
+            user_id,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn list_goal_met_entry_dates_by_user(
+        &self,
+        user_id: i64,
+        goal: i64,
+    ) -> Result<Vec<AppDate>, Error> {
+        query_file!(
+            "src/sql/list_goal_met_entry_dates_by_user.sql",
+            user_id,
+            goal
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map(|records| records.iter().map(|record| record.date.into()).collect())
+    }
+
+    async fn list_entry_summaries_by_user_and_date_range(
+        &self,
+        user_id: i64,
+        from: Option<Date>,
+        to: Option<Date>,
+        after: Option<Date>,
+        preview_length: i64,
+        limit: i64,
+    ) -> Result<Vec<DbEntrySummary>, Error> {
+        query_file_as!(
+            DbEntrySummary,
+            "src/sql/list_entry_summaries_by_user_and_date_range.sql",
+            preview_length,
+            user_id,
+            from,
+            to,
+            after,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,1055 @@
+// the same tests for every implementation of `Repository`. sqlite runs in
+// memory. postgres is ignored by default and needs `TEST_POSTGRES_URL`, it runs
+// in a database of its own which is dropped afterwards, e.g.
+// TEST_POSTGRES_URL=postgres://postgres@localhost/postgres cargo test -- --include-ignored
+
+use std::str::FromStr;
+
+use rand_core::{OsRng, RngCore};
+use serde_json::json;
+use sqlx::{
+    Connection, Executor, PgConnection,
+    postgres::{PgConnectOptions, PgPoolOptions},
+    sqlite::SqlitePoolOptions,
+};
+use time::{Duration, OffsetDateTime, macros::date};
+
+use super::*;
+use crate::{
+    migrate::{POSTGRES_MIGRATOR, SQLITE_MIGRATOR},
+    search::{self, SearchQuery},
+};
+
+pub(crate) async fn sqlite_repository() -> SqliteRepository {
+    // every connection would get a database of its own
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    SQLITE_MIGRATOR.run(&pool).await.unwrap();
+    SqliteRepository::new(pool)
+}
+
+struct PostgresDatabase {
+    url: String,
+    name: String,
+    pool: PgPool,
+    repo: PostgresRepository,
+}
+
+impl PostgresDatabase {
+    async fn create() -> Self {
+        let url = std::env::var("TEST_POSTGRES_URL")
+            .expect("TEST_POSTGRES_URL must be set for the postgres tests");
+        let name = format!("threepages_test_{:016x}", OsRng.next_u64());
+        let mut conn = PgConnection::connect(&url).await.unwrap();
+        conn.execute(format!(r#"CREATE DATABASE "{}""#, name).as_str())
+            .await
+            .unwrap();
+
+        let options = PgConnectOptions::from_str(&url).unwrap().database(&name);
+        let pool = PgPoolOptions::new().connect_with(options).await.unwrap();
+        POSTGRES_MIGRATOR.run(&pool).await.unwrap();
+        PostgresDatabase {
+            url,
+            name,
+            pool: pool.clone(),
+            repo: PostgresRepository::new(pool),
+        }
+    }
+
+    async fn drop(self) {
+        self.pool.close().await;
+        let mut conn = PgConnection::connect(&self.url).await.unwrap();
+        conn.execute(format!(r#"DROP DATABASE "{}" WITH (FORCE)"#, self.name).as_str())
+            .await
+            .unwrap();
+    }
+}
+
+/// a `#[tokio::test]` per backend for each test function
+macro_rules! repository_tests {
+    ($($name:ident),* $(,)?) => {
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(&super::sqlite_repository().await).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            $(
+                #[tokio::test]
+                #[ignore = "needs TEST_POSTGRES_URL"]
+                async fn $name() {
+                    let db = super::PostgresDatabase::create().await;
+                    // the database is dropped even if the test fails
+                    let result = futures_util::FutureExt::catch_unwind(
+                        std::panic::AssertUnwindSafe(super::$name(&db.repo)),
+                    )
+                    .await;
+                    db.drop().await;
+                    if let Err(panic) = result {
+                        std::panic::resume_unwind(panic);
+                    }
+                }
+            )*
+        }
+    };
+}
+
+repository_tests!(
+    users,
+    entries,
+    entry_revisions,
+    trash,
+    trash_purge,
+    entry_pages,
+    stats,
+    summaries,
+    search,
+    search_index,
+    word_counts,
+    sessions,
+    password_reset_tokens,
+    rate_limit_buckets,
+    rate_limit_concurrent_takes,
+    totp,
+);
+
+fn text(text: &str) -> EntryText {
+    let content = serde_json::from_value(json!({
+        "type": "doc",
+        "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": text }] }],
+    }))
+    .unwrap();
+    EntryText::new(&content).unwrap()
+}
+
+pub(crate) async fn create_user(repo: &dyn Repository, email: &str) -> i64 {
+    repo.create_user(email, "Name", "hash").await.unwrap();
+    repo.get_user_id_by_email(email).await.unwrap().unwrap()
+}
+
+async fn users(repo: &dyn Repository) {
+    assert_eq!(
+        repo.get_user_id_by_email("a@example.com").await.unwrap(),
+        None
+    );
+    let id = create_user(repo, "a@example.com").await;
+
+    let err = repo
+        .create_user("a@example.com", "Other", "hash")
+        .await
+        .unwrap_err();
+    assert!(is_unique_violation(&err));
+
+    let user = repo.get_user_by_id(id).await.unwrap().unwrap();
+    assert_eq!(user.email, "a@example.com");
+    assert_eq!(user.name, "Name");
+    assert_eq!(user.timezone, "UTC");
+
+    repo.update_user_by_id(
+        Some("New"),
+        None,
+        None,
+        Some(500),
+        Some("Europe/Berlin"),
+        id,
+    )
+    .await
+    .unwrap();
+    repo.update_user_password_by_id("new hash", id)
+        .await
+        .unwrap();
+    let user = repo
+        .get_user_by_email("a@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.name, "New");
+    assert_eq!(user.password, "new hash");
+    assert_eq!(user.daily_word_goal, 500);
+    assert_eq!(user.timezone, "Europe/Berlin");
+
+    repo.create_entry(id, date!(2026 - 01 - 01), text("hello"))
+        .await
+        .unwrap();
+    repo.delete_user_by_id(id).await.unwrap();
+    assert!(repo.get_user_by_id(id).await.unwrap().is_none());
+    assert!(
+        repo.get_entry_by_user_and_date(id, date!(2026 - 01 - 01))
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+async fn entries(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    let date = date!(2026 - 01 - 02);
+
+    let revision = repo
+        .create_entry(user_id, date, text("one two"))
+        .await
+        .unwrap();
+    assert_eq!(revision, Some(1));
+    assert_eq!(
+        repo.create_entry(user_id, date, text("three"))
+            .await
+            .unwrap(),
+        None
+    );
+
+    let entry = repo
+        .get_entry_by_user_and_date(user_id, date)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.date, AppDate(date));
+    assert_eq!(entry.word_count, 2);
+    assert_eq!(entry.revision, 1);
+    assert_eq!(entry.text, text("one two").text);
+
+    let update = |revision| {
+        repo.update_entry_text_by_user_and_date(
+            text("one two three"),
+            user_id,
+            date,
+            revision,
+            Duration::ZERO,
+        )
+    };
+    assert_eq!(update(Some(2)).await.unwrap(), None);
+    assert_eq!(update(Some(1)).await.unwrap(), Some(2));
+    assert_eq!(update(None).await.unwrap(), Some(3));
+
+    let entry = repo
+        .get_entry_by_user_and_date(user_id, date)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.word_count, 3);
+    assert_eq!(entry.revision, 3);
+
+    assert_eq!(
+        repo.list_entry_dates_by_user(user_id).await.unwrap(),
+        vec![AppDate(date)]
+    );
+    let other_user_id = create_user(repo, "b@example.com").await;
+    assert!(
+        repo.list_entry_dates_by_user(other_user_id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+async fn entry_revisions(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    let date = date!(2026 - 01 - 02);
+    repo.create_entry(user_id, date, text("one")).await.unwrap();
+
+    for word in ["two", "three"] {
+        repo.update_entry_text_by_user_and_date(text(word), user_id, date, None, Duration::HOUR)
+            .await
+            .unwrap();
+    }
+    // the second update was within the snapshot interval
+    let revisions = repo
+        .list_entry_revisions_by_user_and_date(user_id, date)
+        .await
+        .unwrap();
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].revision, 1);
+    assert_eq!(revisions[0].word_count, 1);
+
+    let revision = repo
+        .get_entry_revision_by_user_and_date_and_id(user_id, date, revisions[0].id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(revision.text, text("one").text);
+    assert!(
+        repo.get_entry_revision_by_user_and_date_and_id(
+            user_id,
+            date!(2026 - 01 - 03),
+            revision.id
+        )
+        .await
+        .unwrap()
+        .is_none()
+    );
+
+    // a rejected update doesn't keep a snapshot
+    repo.update_entry_text_by_user_and_date(text("four"), user_id, date, Some(1), Duration::ZERO)
+        .await
+        .unwrap();
+    let revisions = repo
+        .list_entry_revisions_by_user_and_date(user_id, date)
+        .await
+        .unwrap();
+    assert_eq!(revisions.len(), 1);
+}
+
+async fn trash(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    let date = date!(2026 - 01 - 02);
+    repo.create_entry(user_id, date, text("one")).await.unwrap();
+
+    assert!(
+        !repo
+            .restore_entry_by_user_and_date(user_id, date)
+            .await
+            .unwrap()
+    );
+    // only trashed entries are purged
+    repo.purge_entry_by_user_and_date(user_id, date)
+        .await
+        .unwrap();
+    assert!(
+        repo.get_entry_by_user_and_date(user_id, date)
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    repo.trash_entry_by_user_and_date(user_id, date)
+        .await
+        .unwrap();
+    assert!(
+        repo.get_entry_by_user_and_date(user_id, date)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        repo.list_entry_dates_by_user(user_id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    let trashed = repo.list_trashed_entries_by_user(user_id).await.unwrap();
+    assert_eq!(trashed.len(), 1);
+    assert_eq!(trashed[0].date, AppDate(date));
+    assert_eq!(trashed[0].word_count, 1);
+
+    assert!(
+        repo.restore_entry_by_user_and_date(user_id, date)
+            .await
+            .unwrap()
+    );
+    assert!(
+        repo.list_trashed_entries_by_user(user_id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // creating an entry replaces the trashed one
+    repo.trash_entry_by_user_and_date(user_id, date)
+        .await
+        .unwrap();
+    assert_eq!(
+        repo.create_entry(user_id, date, text("two three"))
+            .await
+            .unwrap(),
+        Some(2)
+    );
+    let revisions = repo
+        .list_entry_revisions_by_user_and_date(user_id, date)
+        .await
+        .unwrap();
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].word_count, 1);
+
+    assert_eq!(
+        repo.purge_entries_by_deleted_before(Duration::DAY)
+            .await
+            .unwrap(),
+        0
+    );
+    repo.trash_entry_by_user_and_date(user_id, date)
+        .await
+        .unwrap();
+    repo.purge_entry_by_user_and_date(user_id, date)
+        .await
+        .unwrap();
+    assert!(
+        repo.list_trashed_entries_by_user(user_id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    repo.create_entry(user_id, date, text("one")).await.unwrap();
+    repo.trash_entry_by_user_and_date(user_id, date)
+        .await
+        .unwrap();
+    repo.purge_entries_by_user(user_id).await.unwrap();
+    assert!(
+        repo.list_trashed_entries_by_user(user_id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+async fn trash_purge(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    let trashed = date!(2026 - 01 - 01);
+    let kept = date!(2026 - 01 - 02);
+    repo.create_entry(user_id, trashed, text("one"))
+        .await
+        .unwrap();
+    repo.create_entry(user_id, kept, text("two")).await.unwrap();
+    repo.trash_entry_by_user_and_date(user_id, trashed)
+        .await
+        .unwrap();
+
+    // still within the retention
+    assert_eq!(
+        repo.purge_entries_by_deleted_before(Duration::days(1))
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        repo.list_trashed_entries_by_user(user_id)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // sqlite only stores whole seconds
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    assert_eq!(
+        repo.purge_entries_by_deleted_before(Duration::ZERO)
+            .await
+            .unwrap(),
+        1
+    );
+    assert!(
+        repo.list_trashed_entries_by_user(user_id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    // entries which are not in the trash are never purged
+    assert!(
+        repo.get_entry_by_user_and_date(user_id, kept)
+            .await
+            .unwrap()
+            .is_some()
+    );
+}
+
+async fn entry_pages(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    let dates = [
+        date!(2026 - 01 - 03),
+        date!(2026 - 01 - 01),
+        date!(2026 - 01 - 02),
+    ];
+    for date in dates {
+        repo.create_entry(user_id, date, text("one")).await.unwrap();
+    }
+
+    let page = repo
+        .list_entries_by_user_after_date(user_id, None, 2)
+        .await
+        .unwrap();
+    let page: Vec<_> = page.iter().map(|entry| entry.date.0).collect();
+    assert_eq!(page, [date!(2026 - 01 - 01), date!(2026 - 01 - 02)]);
+    let page = repo
+        .list_entries_by_user_after_date(user_id, Some(date!(2026 - 01 - 02)), 2)
+        .await
+        .unwrap();
+    let page: Vec<_> = page.iter().map(|entry| entry.date.0).collect();
+    assert_eq!(page, [date!(2026 - 01 - 03)]);
+}
+
+async fn stats(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    let stats = repo.get_entry_stats_by_user(user_id).await.unwrap();
+    assert_eq!((stats.entries, stats.words), (0, 0));
+    assert_eq!(stats.average_words, 0.0);
+    assert!(
+        repo.get_longest_entry_by_user(user_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // a sunday, a monday and a sunday in february
+    for (date, words) in [
+        (date!(2026 - 01 - 04), "one two three"),
+        (date!(2026 - 01 - 05), "one two three four five"),
+        (date!(2026 - 02 - 01), "one"),
+        (date!(2026 - 02 - 02), "one two three four five six"),
+    ] {
+        repo.create_entry(user_id, date, text(words)).await.unwrap();
+    }
+    repo.trash_entry_by_user_and_date(user_id, date!(2026 - 02 - 02))
+        .await
+        .unwrap();
+
+    let stats = repo.get_entry_stats_by_user(user_id).await.unwrap();
+    assert_eq!((stats.entries, stats.words), (3, 9));
+    assert_eq!(stats.average_words, 3.0);
+
+    let longest = repo
+        .get_longest_entry_by_user(user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(longest.date, AppDate(date!(2026 - 01 - 05)));
+    assert_eq!(longest.word_count, 5);
+
+    let buckets = |buckets: Vec<DbEntryHistogramBucket>| {
+        let mut buckets: Vec<_> = buckets
+            .into_iter()
+            .map(|bucket| (bucket.key, bucket.entries, bucket.words))
+            .collect();
+        buckets.sort();
+        buckets
+    };
+    assert_eq!(
+        buckets(repo.list_weekday_stats_by_user(user_id).await.unwrap()),
+        [(0, 2, 4), (1, 1, 5)]
+    );
+    assert_eq!(
+        buckets(repo.list_month_stats_by_user(user_id).await.unwrap()),
+        [(1, 2, 8), (2, 1, 1)]
+    );
+
+    let word_counts = repo
+        .list_entry_word_counts_by_user_and_date_range(
+            user_id,
+            date!(2026 - 01 - 05),
+            date!(2026 - 02 - 01),
+        )
+        .await
+        .unwrap();
+    let word_counts: Vec<_> = word_counts
+        .iter()
+        .map(|entry| (entry.date.0, entry.word_count))
+        .collect();
+    assert_eq!(
+        word_counts,
+        [(date!(2026 - 01 - 05), 5), (date!(2026 - 02 - 01), 1)]
+    );
+
+    assert_eq!(
+        repo.list_goal_met_entry_dates_by_user(user_id, 3)
+            .await
+            .unwrap(),
+        [
+            AppDate(date!(2026 - 01 - 04)),
+            AppDate(date!(2026 - 01 - 05))
+        ]
+    );
+}
+
+async fn summaries(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    for (date, words) in [
+        (date!(2026 - 01 - 01), "first entry"),
+        (date!(2026 - 01 - 02), "second entry"),
+        (date!(2026 - 01 - 03), "third entry"),
+    ] {
+        repo.create_entry(user_id, date, text(words)).await.unwrap();
+    }
+
+    let summaries = |from, to, after, limit| {
+        repo.list_entry_summaries_by_user_and_date_range(user_id, from, to, after, 5, limit)
+    };
+    let all = summaries(None, None, None, 10).await.unwrap();
+    let previews: Vec<_> = all
+        .iter()
+        .map(|summary| (summary.date.0, summary.preview.as_str()))
+        .collect();
+    assert_eq!(
+        previews,
+        [
+            (date!(2026 - 01 - 01), "first"),
+            (date!(2026 - 01 - 02), "secon"),
+            (date!(2026 - 01 - 03), "third"),
+        ]
+    );
+
+    let dates = |summaries: Vec<DbEntrySummary>| -> Vec<Date> {
+        summaries.iter().map(|summary| summary.date.0).collect()
+    };
+    assert_eq!(
+        dates(
+            summaries(Some(date!(2026 - 01 - 02)), None, None, 10)
+                .await
+                .unwrap()
+        ),
+        [date!(2026 - 01 - 02), date!(2026 - 01 - 03)]
+    );
+    assert_eq!(
+        dates(
+            summaries(None, Some(date!(2026 - 01 - 02)), None, 10)
+                .await
+                .unwrap()
+        ),
+        [date!(2026 - 01 - 01), date!(2026 - 01 - 02)]
+    );
+    assert_eq!(
+        dates(
+            summaries(None, None, Some(date!(2026 - 01 - 01)), 1)
+                .await
+                .unwrap()
+        ),
+        [date!(2026 - 01 - 02)]
+    );
+}
+
+async fn search(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    repo.create_entry(user_id, date!(2026 - 01 - 01), text("the quick brown fox"))
+        .await
+        .unwrap();
+    repo.create_entry(user_id, date!(2026 - 01 - 02), text("the lazy dog"))
+        .await
+        .unwrap();
+    let other_user_id = create_user(repo, "b@example.com").await;
+    repo.create_entry(other_user_id, date!(2026 - 01 - 01), text("the quick fox"))
+        .await
+        .unwrap();
+
+    let search = |query: &str| {
+        let query = SearchQuery::parse(query).unwrap();
+        async move {
+            let results = repo
+                .search_entries_by_user(user_id, &query, 10, 0)
+                .await
+                .unwrap();
+            let mut dates: Vec<_> = results.iter().map(|result| result.date.0).collect();
+            dates.sort();
+            (dates, results)
+        }
+    };
+
+    let (dates, results) = search("quick").await;
+    assert_eq!(dates, [date!(2026 - 01 - 01)]);
+    assert!(results[0].snippet.contains(&format!(
+        "{}quick{}",
+        search::HIGHLIGHT_START,
+        search::HIGHLIGHT_END
+    )));
+    assert_eq!(search("qui*").await.0, [date!(2026 - 01 - 01)]);
+    assert_eq!(search("\"brown fox\"").await.0, [date!(2026 - 01 - 01)]);
+    assert!(search("\"fox brown\"").await.0.is_empty());
+    assert_eq!(
+        search("fox OR dog").await.0,
+        [date!(2026 - 01 - 01), date!(2026 - 01 - 02)]
+    );
+    assert_eq!(search("the NOT fox").await.0, [date!(2026 - 01 - 02)]);
+    assert_eq!(search("(fox OR dog) lazy").await.0, [date!(2026 - 01 - 02)]);
+
+    // the best match comes first
+    repo.create_entry(user_id, date!(2026 - 01 - 03), text("dog dog dog"))
+        .await
+        .unwrap();
+    let (_, results) = search("dog").await;
+    assert_eq!(results[0].date, AppDate(date!(2026 - 01 - 03)));
+    let results = repo
+        .search_entries_by_user(user_id, &SearchQuery::parse("dog").unwrap(), 1, 1)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].date, AppDate(date!(2026 - 01 - 02)));
+
+    repo.trash_entry_by_user_and_date(user_id, date!(2026 - 01 - 01))
+        .await
+        .unwrap();
+    assert!(search("quick").await.0.is_empty());
+
+    // the index follows updates
+    repo.update_entry_text_by_user_and_date(
+        text("a slow cat"),
+        user_id,
+        date!(2026 - 01 - 02),
+        None,
+        Duration::ZERO,
+    )
+    .await
+    .unwrap();
+    assert!(search("lazy").await.0.is_empty());
+    assert_eq!(search("cat").await.0, [date!(2026 - 01 - 02)]);
+}
+
+async fn search_index(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    let date = date!(2026 - 01 - 01);
+    repo.create_entry(user_id, date, text("indexed"))
+        .await
+        .unwrap();
+    assert!(
+        repo.list_entries_without_search_text(10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    repo.upsert_entry_search_text_by_user_and_date(user_id, date, "reindexed")
+        .await
+        .unwrap();
+    let results = repo
+        .search_entries_by_user(user_id, &SearchQuery::parse("reindexed").unwrap(), 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(
+        repo.search_entries_by_user(user_id, &SearchQuery::parse("indexed").unwrap(), 10, 0)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+async fn word_counts(repo: &dyn Repository) {
+    let first_user_id = create_user(repo, "a@example.com").await;
+    let second_user_id = create_user(repo, "b@example.com").await;
+    for user_id in [second_user_id, first_user_id] {
+        for date in [date!(2026 - 01 - 02), date!(2026 - 01 - 01)] {
+            repo.create_entry(user_id, date, text("one two"))
+                .await
+                .unwrap();
+        }
+    }
+    repo.trash_entry_by_user_and_date(first_user_id, date!(2026 - 01 - 02))
+        .await
+        .unwrap();
+
+    let page = repo
+        .list_all_entries_after_key(first_user_id, date!(2026 - 01 - 01), 2)
+        .await
+        .unwrap();
+    let page: Vec<_> = page
+        .iter()
+        .map(|entry| (entry.user_id, entry.date))
+        .collect();
+    assert_eq!(
+        page,
+        [
+            (first_user_id, date!(2026 - 01 - 02)),
+            (second_user_id, date!(2026 - 01 - 01)),
+        ]
+    );
+
+    repo.update_entry_word_count_by_user_and_date(7, second_user_id, date!(2026 - 01 - 01))
+        .await
+        .unwrap();
+    let entry = repo
+        .get_entry_by_user_and_date(second_user_id, date!(2026 - 01 - 01))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((entry.word_count, entry.revision), (7, 1));
+
+    repo.update_entry_text_by_user_and_date(
+        text("three"),
+        second_user_id,
+        date!(2026 - 01 - 01),
+        None,
+        Duration::ZERO,
+    )
+    .await
+    .unwrap();
+    let revisions = repo.list_entry_revisions_after_id(0, 10).await.unwrap();
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].word_count, 7);
+    repo.update_entry_revision_word_count_by_id(2, revisions[0].id)
+        .await
+        .unwrap();
+    let revisions = repo.list_entry_revisions_after_id(0, 10).await.unwrap();
+    assert_eq!(revisions[0].word_count, 2);
+    assert!(
+        repo.list_entry_revisions_after_id(revisions[0].id, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+async fn sessions(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    let expires_at = OffsetDateTime::now_utc() + Duration::DAY;
+    let id = repo
+        .create_session(user_id, "first", expires_at)
+        .await
+        .unwrap();
+    let other_id = repo
+        .create_session(user_id, "other", expires_at)
+        .await
+        .unwrap();
+
+    let session = repo.get_session_by_id(id).await.unwrap().unwrap();
+    assert_eq!(session.user_id, user_id);
+    assert_eq!(session.refresh_token_hash, "first");
+    assert!(session.is_active());
+    // stored with at least second precision
+    assert!((session.expires_at - expires_at).abs() < Duration::SECOND);
+
+    assert!(
+        repo.rotate_session_refresh_token_by_id_and_hash("second", expires_at, id, "first")
+            .await
+            .unwrap()
+    );
+    assert!(
+        !repo
+            .rotate_session_refresh_token_by_id_and_hash("third", expires_at, id, "first")
+            .await
+            .unwrap()
+    );
+
+    repo.revoke_other_sessions_by_user(user_id, id)
+        .await
+        .unwrap();
+    let session = repo.get_session_by_id(other_id).await.unwrap().unwrap();
+    assert!(!session.is_active());
+    assert!(
+        repo.get_session_by_id(id)
+            .await
+            .unwrap()
+            .unwrap()
+            .is_active()
+    );
+
+    repo.revoke_session_by_id(id).await.unwrap();
+    assert!(
+        !repo
+            .get_session_by_id(id)
+            .await
+            .unwrap()
+            .unwrap()
+            .is_active()
+    );
+    assert!(
+        !repo
+            .rotate_session_refresh_token_by_id_and_hash("third", expires_at, id, "second")
+            .await
+            .unwrap()
+    );
+
+    let id = repo
+        .create_session(user_id, "last", expires_at)
+        .await
+        .unwrap();
+    repo.revoke_sessions_by_user(user_id).await.unwrap();
+    assert!(
+        !repo
+            .get_session_by_id(id)
+            .await
+            .unwrap()
+            .unwrap()
+            .is_active()
+    );
+}
+
+async fn password_reset_tokens(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    let expires_at = OffsetDateTime::now_utc() + Duration::HOUR;
+    repo.create_password_reset_token(user_id, "first", expires_at)
+        .await
+        .unwrap();
+    repo.create_password_reset_token(user_id, "second", expires_at)
+        .await
+        .unwrap();
+
+    let token = repo
+        .get_password_reset_token_by_hash("first")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(token.user_id, user_id);
+    assert!(token.is_usable());
+    assert!(
+        repo.use_password_reset_token_by_id(token.id, user_id, "new hash")
+            .await
+            .unwrap()
+    );
+    assert!(
+        !repo
+            .use_password_reset_token_by_id(token.id, user_id, "other hash")
+            .await
+            .unwrap()
+    );
+    // only the first use sets the password
+    let user = repo.get_user_by_id(user_id).await.unwrap().unwrap();
+    assert_eq!(user.password, "new hash");
+
+    repo.delete_unused_password_reset_tokens_by_user(user_id)
+        .await
+        .unwrap();
+    assert!(
+        repo.get_password_reset_token_by_hash("second")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let token = repo
+        .get_password_reset_token_by_hash("first")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!token.is_usable());
+}
+
+async fn rate_limit_buckets(repo: &dyn Repository) {
+    let bucket = TokenBucket::new(2, std::time::Duration::from_secs(10));
+    assert!(
+        repo.get_rate_limit_bucket_by_key("login:a")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let take = |now| repo.take_rate_limit_token_by_key("login:a", bucket, now);
+    assert!(take(1000.0).await.unwrap());
+    assert!(take(1000.0).await.unwrap());
+    assert!(!take(1005.0).await.unwrap());
+    let state = repo
+        .get_rate_limit_bucket_by_key("login:a")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((state.tokens, state.updated_at), (0.0, 1000.0));
+    assert!(take(1010.0).await.unwrap());
+
+    // never above the capacity
+    for _ in 0..3 {
+        repo.return_rate_limit_token_by_key("login:a", bucket, 1010.0)
+            .await
+            .unwrap();
+    }
+    let state = repo
+        .get_rate_limit_bucket_by_key("login:a")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((state.tokens, state.updated_at), (2.0, 1010.0));
+
+    repo.delete_rate_limit_bucket_by_key("login:a")
+        .await
+        .unwrap();
+    assert!(
+        repo.get_rate_limit_bucket_by_key("login:a")
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+async fn rate_limit_concurrent_takes(repo: &dyn Repository) {
+    let bucket = TokenBucket::new(5, std::time::Duration::from_secs(3600));
+    let takes = (0..12).map(|_| repo.take_rate_limit_token_by_key("login:a", bucket, 1000.0));
+    let taken = futures_util::future::join_all(takes).await;
+    assert_eq!(
+        taken
+            .into_iter()
+            .filter(|taken| *taken.as_ref().unwrap())
+            .count(),
+        5
+    );
+}
+
+async fn totp(repo: &dyn Repository) {
+    let user_id = create_user(repo, "a@example.com").await;
+    assert!(repo.get_user_totp_by_user(user_id).await.unwrap().is_none());
+
+    repo.upsert_pending_user_totp(user_id, "first")
+        .await
+        .unwrap();
+    repo.upsert_pending_user_totp(user_id, "second")
+        .await
+        .unwrap();
+    let totp = repo.get_user_totp_by_user(user_id).await.unwrap().unwrap();
+    assert_eq!(totp.secret, "second");
+    assert!(!totp.is_enabled());
+
+    assert!(repo.enable_user_totp_by_user(10, user_id).await.unwrap());
+    assert!(!repo.enable_user_totp_by_user(11, user_id).await.unwrap());
+    // enabled totp is kept
+    repo.upsert_pending_user_totp(user_id, "third")
+        .await
+        .unwrap();
+    let totp = repo.get_user_totp_by_user(user_id).await.unwrap().unwrap();
+    assert_eq!(totp.secret, "second");
+    assert!(totp.is_enabled());
+    assert_eq!(totp.last_used_step, Some(10));
+
+    assert!(
+        !repo
+            .update_user_totp_last_used_step_by_user(10, user_id)
+            .await
+            .unwrap()
+    );
+    assert!(
+        repo.update_user_totp_last_used_step_by_user(12, user_id)
+            .await
+            .unwrap()
+    );
+
+    let codes = ["a".to_string(), "b".to_string()];
+    repo.replace_recovery_codes_by_user(user_id, &codes)
+        .await
+        .unwrap();
+    assert!(
+        repo.use_recovery_code_by_user_and_hash(user_id, "a")
+            .await
+            .unwrap()
+    );
+    assert!(
+        !repo
+            .use_recovery_code_by_user_and_hash(user_id, "a")
+            .await
+            .unwrap()
+    );
+    repo.replace_recovery_codes_by_user(user_id, &["c".to_string()])
+        .await
+        .unwrap();
+    assert!(
+        !repo
+            .use_recovery_code_by_user_and_hash(user_id, "b")
+            .await
+            .unwrap()
+    );
+
+    // the recovery codes go with the totp
+    repo.delete_user_totp_by_user(user_id).await.unwrap();
+    assert!(repo.get_user_totp_by_user(user_id).await.unwrap().is_none());
+    assert!(
+        !repo
+            .use_recovery_code_by_user_and_hash(user_id, "c")
+            .await
+            .unwrap()
+    );
+}
+
+#[test]
+fn backend_from_url() {
+    assert_eq!(
+        Backend::from_url("sqlite:data.db").unwrap(),
+        Backend::Sqlite
+    );
+    assert_eq!(
+        Backend::from_url("postgres://localhost/3pages").unwrap(),
+        Backend::Postgres
+    );
+    assert_eq!(
+        Backend::from_url("postgresql://localhost/3pages").unwrap(),
+        Backend::Postgres
+    );
+    assert!(Backend::from_url("mysql://localhost/3pages").is_err());
+    assert!(Backend::from_url("data.db").is_err());
+}
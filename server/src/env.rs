@@ -1,12 +1,39 @@
 use dotenvy::dotenv;
 use std::env;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::sync::OnceLock;
+use std::thread;
+
+#[derive(Debug, Clone, Copy)]
+pub enum RateLimitStoreKind {
+    Memory,
+    Database,
+}
 
 #[derive(Debug)]
 pub struct Env {
     pub database_url: String,
     pub jwt_secret: String,
     pub port: u16,
+    /// used to build links in mails, e.g. for password reset
+    pub client_base_url: String,
+    /// mails are only logged when not set
+    pub smtp_url: Option<String>,
+    pub mail_from: String,
+    pub mail_sink_dir: Option<PathBuf>,
+    /// the database store survives restarts and is shared between processes
+    pub rate_limit_store: RateLimitStoreKind,
+    // ref: https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#argon2id
+    pub argon2_m_cost: u32,
+    pub argon2_t_cost: u32,
+    pub argon2_p_cost: u32,
+    /// max number of passwords hashed or verified at the same time
+    pub hasher_max_concurrency: usize,
+    /// autosaves within this interval are coalesced into one entry revision
+    pub entry_revision_interval: time::Duration,
+    /// entries in the trash are purged after this
+    pub entry_trash_retention: time::Duration,
 }
 
 // ref: https://doc.rust-lang.org/std/sync/struct.OnceLock.html
@@ -35,10 +62,77 @@ impl Env {
             .expect("PORT must be set")
             .parse::<u16>()
             .expect("PORT must be a valid number");
+        let client_base_url =
+            env::var("CLIENT_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+        let smtp_url = env::var("SMTP_URL").ok();
+        let mail_from =
+            env::var("MAIL_FROM").unwrap_or_else(|_| "3pages <no-reply@localhost>".to_string());
+        let mail_sink_dir = env::var("MAIL_SINK_DIR").ok().map(PathBuf::from);
+        let rate_limit_store = match env::var("RATE_LIMIT_STORE").as_deref() {
+            Err(_) | Ok("memory") => RateLimitStoreKind::Memory,
+            // `sqlite` from before postgres was supported
+            Ok("database" | "sqlite") => RateLimitStoreKind::Database,
+            Ok(_) => return Err("RATE_LIMIT_STORE must be either memory or database"),
+        };
+        let argon2_m_cost = env::var("ARGON2_M_COST")
+            .map(|v| {
+                v.parse::<u32>()
+                    .expect("ARGON2_M_COST must be a valid number")
+            })
+            .unwrap_or(47104);
+        let argon2_t_cost = env::var("ARGON2_T_COST")
+            .map(|v| {
+                v.parse::<u32>()
+                    .expect("ARGON2_T_COST must be a valid number")
+            })
+            .unwrap_or(1);
+        let argon2_p_cost = env::var("ARGON2_P_COST")
+            .map(|v| {
+                v.parse::<u32>()
+                    .expect("ARGON2_P_COST must be a valid number")
+            })
+            .unwrap_or(1);
+        let hasher_max_concurrency = env::var("HASHER_MAX_CONCURRENCY")
+            .map(|v| {
+                // a semaphore without permits would hang every hash
+                v.parse::<NonZeroUsize>()
+                    .expect("HASHER_MAX_CONCURRENCY must be a number of at least 1")
+                    .get()
+            })
+            .unwrap_or_else(|_| thread::available_parallelism().map_or(1, |n| n.get()));
+        let entry_revision_interval = env::var("ENTRY_REVISION_INTERVAL_MINUTES")
+            .map(|v| {
+                // a negative interval would be a malformed sqlite datetime modifier
+                v.parse::<u32>()
+                    .expect("ENTRY_REVISION_INTERVAL_MINUTES must be a number of at least 0")
+            })
+            .map_or(time::Duration::minutes(10), |minutes| {
+                time::Duration::minutes(minutes.into())
+            });
+        let entry_trash_retention = env::var("ENTRY_TRASH_RETENTION_DAYS")
+            .map(|v| {
+                // a negative retention would purge entries as soon as they are trashed
+                v.parse::<u32>()
+                    .expect("ENTRY_TRASH_RETENTION_DAYS must be a number of at least 0")
+            })
+            .map_or(time::Duration::days(30), |days| {
+                time::Duration::days(days.into())
+            });
         let env = Env {
             database_url,
             jwt_secret,
             port,
+            client_base_url,
+            smtp_url,
+            mail_from,
+            mail_sink_dir,
+            rate_limit_store,
+            argon2_m_cost,
+            argon2_t_cost,
+            argon2_p_cost,
+            hasher_max_concurrency,
+            entry_revision_interval,
+            entry_trash_retention,
         };
         Ok(env)
     }
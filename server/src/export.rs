@@ -0,0 +1,194 @@
+// the zip is written into a pipe while it is sent, and the entries are read in
+// pages, so neither the entries nor the archive are in memory at once
+
+use std::sync::Arc;
+
+use async_zip::{
+    Compression, ZipDateTime, ZipDateTimeBuilder, ZipEntryBuilder, tokio::write::ZipFileWriter,
+};
+use axum::body::{Body, Bytes};
+use futures_util::{StreamExt, stream};
+use log::error;
+use serde::Serialize;
+use thiserror::Error;
+use time::OffsetDateTime;
+use tokio::io::{AsyncWriteExt, DuplexStream};
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    datetime::AppDateTime,
+    db::{DbEntry, DbUser, Repository},
+    tiptap::TiptapJsonContent,
+};
+
+/// entries read from the db at once
+const PAGE_SIZE: i64 = 50;
+/// bytes buffered between the zip writer and the response
+const PIPE_CAPACITY: usize = 64 * 1024;
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+pub const MANIFEST_VERSION: i64 = 1;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Db(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    Zip(#[from] async_zip::error::ZipError),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Format(#[from] time::error::Format),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    version: i64,
+    #[serde(rename = "exportedAt")]
+    exported_at: AppDateTime,
+    /// without the password, see `DbUser`
+    user: &'a DbUser,
+    entries: Vec<ManifestEntry>,
+}
+
+/// named like the fields of `DbEntry`
+#[derive(Serialize)]
+struct ManifestEntry {
+    date: String,
+    word_count: i64,
+    revision: i64,
+    #[serde(rename = "createdAt")]
+    created_at: AppDateTime,
+    #[serde(rename = "updatedAt")]
+    updated_at: AppDateTime,
+    markdown: String,
+    json: String,
+}
+
+pub fn filename(now: OffsetDateTime) -> Result<String, time::error::Format> {
+    Ok(format!(
+        "3pages-export-{}.zip",
+        AppDateTime(now).to_yyyy_mm_dd_string()?
+    ))
+}
+
+/// the response body. errors after the response has started can only abort
+/// it, so that clients don't mistake a truncated zip for a complete one.
+pub fn export_body(repo: Arc<dyn Repository>, user: DbUser) -> Body {
+    let (reader, writer) = tokio::io::duplex(PIPE_CAPACITY);
+    let task = tokio::spawn(async move {
+        let result = write_export(&*repo, &user, writer).await;
+        if let Err(err) = &result {
+            error!("failed to export entries of user {}: {}", user.id, err);
+        }
+        result
+    });
+
+    // ends the body with an error if the task failed
+    let result = stream::once(async move {
+        match task.await {
+            Ok(Ok(())) => None,
+            Ok(Err(err)) => Some(Err(std::io::Error::other(err))),
+            Err(err) => Some(Err(std::io::Error::other(err))),
+        }
+    })
+    .filter_map(|result: Option<Result<Bytes, std::io::Error>>| async move { result });
+
+    Body::from_stream(ReaderStream::new(reader).chain(result))
+}
+
+fn zip_datetime(datetime: AppDateTime) -> ZipDateTime {
+    ZipDateTimeBuilder::new()
+        .year(datetime.year())
+        .month(u8::from(datetime.month()).into())
+        .day(datetime.day().into())
+        .hour(datetime.hour().into())
+        .minute(datetime.minute().into())
+        .second(datetime.second().into())
+        .build()
+}
+
+async fn write_file(
+    zip: &mut ZipFileWriter<DuplexStream>,
+    filename: &str,
+    modified: AppDateTime,
+    data: &[u8],
+) -> Result<(), ExportError> {
+    let entry = ZipEntryBuilder::new(filename.to_string().into(), Compression::Deflate)
+        .last_modification_date(zip_datetime(modified))
+        .unix_permissions(0o644);
+    zip.write_entry_whole(entry, data).await?;
+    Ok(())
+}
+
+async fn write_export(
+    repo: &dyn Repository,
+    user: &DbUser,
+    writer: DuplexStream,
+) -> Result<(), ExportError> {
+    let mut zip = ZipFileWriter::with_tokio(writer);
+    let mut entries = Vec::new();
+
+    let mut after = None;
+    loop {
+        let page = repo
+            .list_entries_by_user_after_date(user.id, after, PAGE_SIZE)
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        after = Some(last.date.into());
+        for entry in page {
+            entries.push(write_entry(&mut zip, entry).await?);
+        }
+    }
+
+    let now = AppDateTime(OffsetDateTime::now_utc());
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        exported_at: now,
+        user,
+        entries,
+    };
+    let manifest = serde_json::to_vec_pretty(&manifest)?;
+    write_file(&mut zip, MANIFEST_FILENAME, now, &manifest).await?;
+
+    let mut writer = zip.close().await?.into_inner();
+    writer.shutdown().await?;
+    Ok(())
+}
+
+async fn write_entry(
+    zip: &mut ZipFileWriter<DuplexStream>,
+    entry: DbEntry,
+) -> Result<ManifestEntry, ExportError> {
+    let date = entry.date.to_string();
+    let markdown_filename = format!("entries/{}.md", date);
+    let json_filename = format!("entries/{}.json", date);
+
+    let markdown = serde_json::from_value::<TiptapJsonContent>(entry.text.clone())?.to_markdown();
+    write_file(
+        zip,
+        &markdown_filename,
+        entry.updated_at,
+        markdown.as_bytes(),
+    )
+    .await?;
+    let json = serde_json::to_vec_pretty(&entry.text)?;
+    write_file(zip, &json_filename, entry.updated_at, &json).await?;
+
+    Ok(ManifestEntry {
+        date,
+        word_count: entry.word_count,
+        revision: entry.revision,
+        created_at: entry.created_at,
+        updated_at: entry.updated_at,
+        markdown: markdown_filename,
+        json: json_filename,
+    })
+}
@@ -3,14 +3,19 @@
 use std::collections::HashMap;
 
 use axum::{
-    extract::{FromRequest, Json, Request, rejection::JsonRejection},
-    http::StatusCode,
+    extract::{
+        FromRequest, Json, Request,
+        rejection::{JsonRejection, StringRejection},
+    },
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
-use serde::de::DeserializeOwned;
+use serde::{Deserialize, de::DeserializeOwned};
 use thiserror::Error;
 use validator::Validate;
 
+use crate::{schema::SchemaError, tiptap::TiptapJsonContent};
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ValidatedJson<T>(pub T);
 
@@ -29,13 +34,54 @@ where
     }
 }
 
+#[derive(Deserialize)]
+struct EntryBodyJson {
+    text: TiptapJsonContent,
+}
+
+/// the text of an entry, either as `{ "text": <tiptap json> }` or as markdown
+/// with `Content-Type: text/markdown`, validated against the editor's schema
+#[derive(Debug)]
+pub struct EntryBody(pub TiptapJsonContent);
+
+impl<S> FromRequest<S> for EntryBody
+where
+    S: Send + Sync,
+{
+    type Rejection = ServerError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_markdown = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.to_ascii_lowercase().starts_with("text/markdown"));
+
+        let mut text = if is_markdown {
+            let markdown = String::from_request(req, state).await?;
+            TiptapJsonContent::from_markdown(&markdown)
+        } else {
+            let Json(body) = Json::<EntryBodyJson>::from_request(req, state).await?;
+            body.text
+        };
+        text.validate().map_err(ServerError::SchemaError)?;
+        Ok(EntryBody(text))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ServerError {
     #[error(transparent)]
     ValidationError(#[from] validator::ValidationErrors),
 
+    #[error("invalid document")]
+    SchemaError(Vec<SchemaError>),
+
     #[error(transparent)]
     AxumJsonRejection(#[from] JsonRejection),
+
+    #[error(transparent)]
+    AxumStringRejection(#[from] StringRejection),
 }
 
 impl IntoResponse for ServerError {
@@ -60,11 +106,23 @@ impl IntoResponse for ServerError {
                     .into_response()
             }
 
+            ServerError::SchemaError(errors) => (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "schema_error", "errors": errors })),
+            )
+                .into_response(),
+
             ServerError::AxumJsonRejection(rejection) => {
                 let message = rejection.to_string();
                 let body = Json(serde_json::json!({ "error": message }));
                 (StatusCode::BAD_REQUEST, body).into_response()
             }
+
+            ServerError::AxumStringRejection(rejection) => {
+                let message = rejection.to_string();
+                let body = Json(serde_json::json!({ "error": message }));
+                (rejection.status(), body).into_response()
+            }
         }
         .into_response()
     }
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use argon2::{
+    Algorithm, Argon2, Params, PasswordHash, PasswordVerifier, Version,
+    password_hash::{self, PasswordHasher, SaltString},
+};
+use rand_core::OsRng;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+
+use crate::env::Env;
+
+#[derive(Debug, Error)]
+pub enum HasherError {
+    #[error(transparent)]
+    Params(#[from] argon2::Error),
+
+    #[error(transparent)]
+    PasswordHash(#[from] password_hash::Error),
+
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+
+    #[error(transparent)]
+    Acquire(#[from] tokio::sync::AcquireError),
+}
+
+/// argon2 is cpu and memory heavy by design, so it runs on the blocking thread
+/// pool instead of an async worker, and at most `max_concurrency` at a time
+#[derive(Clone)]
+pub struct Hasher {
+    params: Params,
+    semaphore: Arc<Semaphore>,
+    /// hash of a random password with the same params as `hash`, see
+    /// `verify_dummy`
+    dummy_hash: Arc<str>,
+}
+
+impl Hasher {
+    pub fn new(params: Params, max_concurrency: usize) -> Result<Self, HasherError> {
+        let dummy_hash = hash_password(&params, "3pages-dummy-password")?;
+        Ok(Hasher {
+            params,
+            semaphore: Arc::new(Semaphore::new(max_concurrency)),
+            dummy_hash: dummy_hash.into(),
+        })
+    }
+
+    pub fn from_env() -> Result<Self, HasherError> {
+        let env = Env::get();
+        let params = Params::new(
+            env.argon2_m_cost,
+            env.argon2_t_cost,
+            env.argon2_p_cost,
+            None,
+        )?;
+        Hasher::new(params, env.hasher_max_concurrency)
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T, HasherError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let _permit = self.semaphore.acquire().await?;
+        // ref: https://docs.rs/tokio/1.46.1/tokio/task/fn.spawn_blocking.html
+        Ok(tokio::task::spawn_blocking(f).await?)
+    }
+
+    pub async fn hash(&self, password: &str) -> Result<String, HasherError> {
+        let params = self.params.clone();
+        let password = password.to_string();
+        self.run(move || hash_password(&params, &password)).await?
+    }
+
+    pub async fn verify(&self, password: &str, password_hash: &str) -> Result<bool, HasherError> {
+        let password = password.to_string();
+        let password_hash = password_hash.to_string();
+        self.run(move || verify_password(&password, &password_hash))
+            .await
+    }
+
+    /// costs as much as `verify` for an existing user, to avoid leaking whether
+    /// an account exists through response times
+    pub async fn verify_dummy(&self, password: &str) -> Result<(), HasherError> {
+        let dummy_hash = self.dummy_hash.clone();
+        self.verify(password, &dummy_hash).await?;
+        Ok(())
+    }
+
+    /// true if the hash was created with other params than the configured
+    /// ones, e.g. by the old js server or before the params were changed
+    pub fn needs_rehash(&self, password_hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(password_hash) else {
+            return true;
+        };
+        if parsed.algorithm != Algorithm::Argon2id.ident()
+            || parsed.version != Some(Version::V0x13.into())
+        {
+            return true;
+        }
+        match Params::try_from(&parsed) {
+            Ok(params) => {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+fn hash_password(params: &Params, password: &str) -> Result<String, HasherError> {
+    // ref: https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#argon2id
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone());
+    let salt = SaltString::generate(&mut OsRng);
+
+    Ok(argon2
+        .hash_password(password.as_bytes(), &salt)
+        .map(|ph| ph.to_string())?)
+}
+
+/// the params are read from the hash itself, so hashes with outdated params
+/// still verify
+fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash).is_ok_and(|parsed| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hasher() -> Hasher {
+        // same as the defaults in `Env`
+        Hasher::new(Params::new(47104, 1, 1, None).unwrap(), 2).unwrap()
+    }
+
+    #[tokio::test]
+    /// to ensure that we can verify existing password hashes generated by js
+    /// server.
+    async fn verify_existing_hash() {
+        let password = "jack@example.com";
+        let old_hash = "$argon2id$v=19$m=47104,t=2,p=1$Wej+XgzGzI6kDYK+8F3DPA$9aeemDzDmYWBr3aUBiUl4m3WvcFUOB1f1+aSfnUM5X4";
+        assert!(hasher().verify(password, old_hash).await.unwrap());
+    }
+
+    #[tokio::test]
+    /// to ensure that we can verify newly created password hashes
+    async fn verify_new_hash() {
+        let hasher = hasher();
+        let password = "jack@example.com";
+        let new_hash = hasher.hash(password).await.unwrap();
+        assert!(hasher.verify(password, &new_hash).await.unwrap());
+        assert!(!hasher.verify("jill@example.com", &new_hash).await.unwrap());
+    }
+
+    #[tokio::test]
+    /// the dummy hash must cost the same as a real one
+    async fn dummy_hash_matches_current_params() {
+        let hasher = hasher();
+        assert!(!hasher.needs_rehash(&hasher.dummy_hash));
+        hasher.verify_dummy("jack@example.com").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn outdated_hashes_need_rehash() {
+        let hasher = hasher();
+        let new_hash = hasher.hash("jack@example.com").await.unwrap();
+        assert!(!hasher.needs_rehash(&new_hash));
+
+        // generated by the js server with t=2
+        let old_hash = "$argon2id$v=19$m=47104,t=2,p=1$Wej+XgzGzI6kDYK+8F3DPA$9aeemDzDmYWBr3aUBiUl4m3WvcFUOB1f1+aSfnUM5X4";
+        assert!(hasher.needs_rehash(old_hash));
+
+        let cheaper = Hasher::new(Params::new(19456, 2, 1, None).unwrap(), 1).unwrap();
+        assert!(hasher.needs_rehash(&cheaper.dummy_hash));
+        assert!(hasher.needs_rehash("not a phc string"));
+    }
+}
@@ -0,0 +1,342 @@
+// renders entries like the client's editor does, see the extensions in
+// client/src/pages/writings/[date].tsx
+// ref: https://github.com/ueberdosis/tiptap/tree/v3.2.0/packages
+
+use serde_json::Value;
+
+use crate::tiptap::{TiptapJsonContent, TiptapMark};
+
+/// same as the `protocols` of `TiptapLink` in the client
+const LINK_PROTOCOLS: [&str; 6] = ["http", "https", "mailto", "tel", "ftp", "magnet"];
+/// the `HTMLAttributes` of `Paragraph` in the client
+const PARAGRAPH_CLASS: &str = "mt-0 mb-3";
+
+impl TiptapJsonContent {
+    /// an html fragment, all text and attributes are escaped and links with
+    /// other protocols are rendered as plain text
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        push_node(&mut html, self);
+        html
+    }
+}
+
+/// a standalone page for printing and sharing
+pub fn entry_page(title: &str, content: &TiptapJsonContent) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n</head>\n<body>\n<article>{}</article>\n</body>\n</html>\n",
+        escape(title),
+        content.to_html()
+    )
+}
+
+/// ref: https://cheatsheetseries.owasp.org/cheatsheets/Cross_Site_Scripting_Prevention_Cheat_Sheet.html#output-encoding-for-html-contexts
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// like `isAllowedUri` of the link extension: urls without a scheme, e.g.
+/// relative ones, are allowed too
+/// ref: https://github.com/ueberdosis/tiptap/blob/v3.2.0/packages/extension-link/src/link.ts
+pub fn is_allowed_href(href: &str) -> bool {
+    // browsers ignore these, e.g. in `java\nscript:`
+    let href = href
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>();
+    let Some((scheme, _)) = href.split_once(':') else {
+        return true;
+    };
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !is_scheme {
+        // the colon is part of the path, query, etc.
+        return true;
+    }
+    LINK_PROTOCOLS
+        .iter()
+        .any(|protocol| scheme.eq_ignore_ascii_case(protocol))
+}
+
+fn attr<'a>(jc: &'a TiptapJsonContent, name: &str) -> Option<&'a Value> {
+    jc.attrs.as_ref().and_then(|attrs| attrs.get(name))
+}
+
+fn push_children(html: &mut String, jc: &TiptapJsonContent) {
+    let mut active: Vec<&TiptapMark> = Vec::new();
+    for child in jc.content.as_deref().unwrap_or_default() {
+        let mut marks = child
+            .marks
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .filter(|mark| mark_rank(mark).is_some())
+            .collect::<Vec<_>>();
+        marks.sort_by_key(|mark| mark_rank(mark));
+
+        // marks shared with the previous node stay open
+        let keep = active
+            .iter()
+            .zip(&marks)
+            .take_while(|(a, b)| a.type_ == b.type_ && a.attrs == b.attrs)
+            .count();
+        while active.len() > keep {
+            if let Some(mark) = active.pop() {
+                html.push_str(close_mark(mark));
+            }
+        }
+        for mark in &marks[keep..] {
+            html.push_str(&open_mark(mark));
+            active.push(mark);
+        }
+        push_node(html, child);
+    }
+    while let Some(mark) = active.pop() {
+        html.push_str(close_mark(mark));
+    }
+}
+
+fn push_element(html: &mut String, tag: &str, attrs: &str, jc: &TiptapJsonContent) {
+    html.push_str(&format!("<{}{}>", tag, attrs));
+    push_children(html, jc);
+    html.push_str(&format!("</{}>", tag));
+}
+
+fn push_node(html: &mut String, jc: &TiptapJsonContent) {
+    if let Some(text) = &jc.text {
+        html.push_str(&escape(text));
+        return;
+    }
+    match jc.type_.as_deref() {
+        Some("paragraph") => {
+            push_element(html, "p", &format!(" class=\"{}\"", PARAGRAPH_CLASS), jc)
+        }
+        Some("heading") => {
+            let level = attr(jc, "level")
+                .and_then(Value::as_u64)
+                .unwrap_or(1)
+                .clamp(1, 6);
+            push_element(html, &format!("h{}", level), "", jc);
+        }
+        Some("blockquote") => push_element(html, "blockquote", "", jc),
+        Some("bulletList") => push_element(html, "ul", "", jc),
+        Some("orderedList") => {
+            let start = attr(jc, "start").and_then(Value::as_u64).unwrap_or(1);
+            // like the extension, which only renders other starts
+            let attrs = if start == 1 {
+                String::new()
+            } else {
+                format!(" start=\"{}\"", start)
+            };
+            push_element(html, "ol", &attrs, jc);
+        }
+        Some("listItem") => push_element(html, "li", "", jc),
+        Some("codeBlock") => {
+            let language = attr(jc, "language")
+                .and_then(Value::as_str)
+                .filter(|language| !language.is_empty());
+            html.push_str("<pre spellcheck=\"false\">");
+            match language {
+                Some(language) => push_element(
+                    html,
+                    "code",
+                    &format!(" class=\"language-{}\"", escape(language)),
+                    jc,
+                ),
+                None => push_element(html, "code", "", jc),
+            }
+            html.push_str("</pre>");
+        }
+        Some("horizontalRule") => html.push_str("<hr>"),
+        Some("hardBreak") => html.push_str("<br>"),
+        // doc, and nodes the client doesn't know, which only keep their text
+        _ => push_children(html, jc),
+    }
+}
+
+/// the order in which the editor nests marks, links have a higher priority
+/// than the others, which follow the order of the extensions
+fn mark_rank(mark: &TiptapMark) -> Option<usize> {
+    match mark.type_.as_str() {
+        "link" => Some(0),
+        "bold" => Some(1),
+        "code" => Some(2),
+        "italic" => Some(3),
+        "strike" => Some(4),
+        "underline" => Some(5),
+        _ => None,
+    }
+}
+
+fn link_href(mark: &TiptapMark) -> Option<&str> {
+    mark.attrs
+        .as_ref()
+        .and_then(|attrs| attrs.get("href"))
+        .and_then(Value::as_str)
+        .filter(|href| is_allowed_href(href))
+}
+
+fn open_mark(mark: &TiptapMark) -> String {
+    match mark.type_.as_str() {
+        "link" => match link_href(mark) {
+            Some(href) => format!(
+                "<a target=\"_blank\" rel=\"noopener noreferrer nofollow\" href=\"{}\">",
+                escape(href)
+            ),
+            None => String::new(),
+        },
+        "bold" => "<strong>".to_string(),
+        "code" => "<code>".to_string(),
+        "italic" => "<em>".to_string(),
+        "strike" => "<s>".to_string(),
+        "underline" => "<u>".to_string(),
+        _ => String::new(),
+    }
+}
+
+fn close_mark(mark: &TiptapMark) -> &'static str {
+    match mark.type_.as_str() {
+        "link" if link_href(mark).is_some() => "</a>",
+        "bold" => "</strong>",
+        "code" => "</code>",
+        "italic" => "</em>",
+        "strike" => "</s>",
+        "underline" => "</u>",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(content: Value) -> String {
+        serde_json::from_value::<TiptapJsonContent>(json!({ "type": "doc", "content": content }))
+            .unwrap()
+            .to_html()
+    }
+
+    fn text(text: &str, marks: Value) -> Value {
+        json!({ "type": "text", "text": text, "marks": marks })
+    }
+
+    fn link(href: &str) -> Value {
+        json!({ "type": "link", "attrs": { "href": href } })
+    }
+
+    #[test]
+    fn blocks() {
+        let html = render(json!([
+            { "type": "heading", "attrs": { "level": 2 }, "content": [text("Title", json!(null))] },
+            { "type": "paragraph" },
+            { "type": "blockquote", "content": [{ "type": "horizontalRule" }] },
+            {
+                "type": "orderedList",
+                "attrs": { "start": 3 },
+                "content": [{ "type": "listItem", "content": [{
+                    "type": "paragraph",
+                    "content": [text("a", json!(null)), { "type": "hardBreak" }, text("b", json!(null))]
+                }] }]
+            },
+            { "type": "bulletList", "content": [{ "type": "listItem" }] },
+            { "type": "codeBlock", "attrs": { "language": "rust" }, "content": [text("a < b", json!(null))] },
+        ]));
+        assert_eq!(
+            html,
+            concat!(
+                "<h2>Title</h2>",
+                "<p class=\"mt-0 mb-3\"></p>",
+                "<blockquote><hr></blockquote>",
+                "<ol start=\"3\"><li><p class=\"mt-0 mb-3\">a<br>b</p></li></ol>",
+                "<ul><li></li></ul>",
+                "<pre spellcheck=\"false\"><code class=\"language-rust\">a &lt; b</code></pre>",
+            )
+        );
+    }
+
+    #[test]
+    fn marks_spanning_text_nodes() {
+        let html = render(json!([{ "type": "paragraph", "content": [
+            text("a ", json!([{ "type": "bold" }, link("https://example.com")])),
+            text("link", json!([link("https://example.com"), { "type": "italic" }])),
+            text(" ", json!(null)),
+            text("x", json!([{ "type": "code" }, { "type": "strike" }, { "type": "underline" }])),
+        ] }]));
+        assert_eq!(
+            html,
+            concat!(
+                "<p class=\"mt-0 mb-3\">",
+                "<a target=\"_blank\" rel=\"noopener noreferrer nofollow\" href=\"https://example.com\">",
+                "<strong>a </strong><em>link</em></a> ",
+                "<code><s><u>x</u></s></code></p>",
+            )
+        );
+    }
+
+    #[test]
+    fn escapes_text_and_attributes() {
+        let html = render(json!([
+            { "type": "paragraph", "content": [text("<script>alert('x')</script> & \"", json!(null))] },
+            { "type": "codeBlock", "attrs": { "language": "\"><script>" } },
+            { "type": "paragraph", "content": [text("x", json!([link("https://e.com/?a=1&b=\"2\"")]))] },
+        ]));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(&#x27;x&#x27;)&lt;/script&gt; &amp; &quot;"));
+        assert!(html.contains("class=\"language-&quot;&gt;&lt;script&gt;\""));
+        assert!(html.contains("href=\"https://e.com/?a=1&amp;b=&quot;2&quot;\""));
+    }
+
+    #[test]
+    fn link_protocols() {
+        for href in [
+            "https://example.com",
+            "HTTP://example.com",
+            "mailto:jack@example.com",
+            "tel:+123",
+            "ftp://example.com",
+            "magnet:?xt=urn:btih:abc",
+            "/relative/path",
+            "example.com/a:b",
+            "#anchor",
+        ] {
+            assert!(is_allowed_href(href), "{}", href);
+        }
+        for href in [
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            " java\nscript:alert(1)",
+            "data:text/html,<script>",
+            "vbscript:x",
+            "ftps://example.com",
+        ] {
+            assert!(!is_allowed_href(href), "{}", href);
+        }
+
+        let html = render(json!([{ "type": "paragraph", "content": [
+            text("x", json!([link("javascript:alert(1)"), { "type": "bold" }])),
+        ] }]));
+        assert_eq!(html, "<p class=\"mt-0 mb-3\"><strong>x</strong></p>");
+    }
+
+    #[test]
+    fn unknown_nodes_and_marks_keep_their_text() {
+        let html = render(json!([
+            { "type": "image", "attrs": { "src": "x" }, "content": [text("<b>", json!([{ "type": "highlight" }]))] },
+        ]));
+        assert_eq!(html, "&lt;b&gt;");
+    }
+}
@@ -0,0 +1,701 @@
+// imports a zip of markdown files named by date, a day one json export (as is
+// or zipped), a zip of journey entries (or a single one), or a zip from
+// `export`
+
+use std::collections::HashMap;
+
+use async_zip::base::read::mem::ZipFileReader;
+use futures_util::io::AsyncReadExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use time::{
+    Date, OffsetDateTime, format_description::well_known::Rfc3339, macros::format_description,
+};
+use time_tz::{OffsetDateTimeExt, timezones};
+
+use crate::{
+    db::{EntryText, Repository},
+    export::MANIFEST_FILENAME,
+    html,
+    tiptap::{TiptapJsonContent, TiptapMark},
+};
+
+/// of the request body
+pub const MAX_IMPORT_SIZE: usize = 64 * 1024 * 1024;
+/// uncompressed, per file in a zip. checked while reading, since the sizes
+/// in the zip can't be trusted.
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+/// uncompressed, of all files read from a zip together
+const MAX_TOTAL_SIZE: u64 = 256 * 1024 * 1024;
+const MAX_FILES: usize = 20_000;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const MARKDOWN_EXTENSIONS: [&str; 3] = [".md", ".markdown", ".txt"];
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("invalid zip: {0}")]
+    Zip(#[from] async_zip::error::ZipError),
+
+    #[error("invalid json: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("larger than {0} bytes uncompressed")]
+    TooLarge(u64),
+
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// what to do with an imported entry for a date which has an entry already
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    #[default]
+    Skip,
+    /// the previous text is kept as an entry revision
+    Overwrite,
+    /// appends the imported text
+    Merge,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Created,
+    Skipped,
+    Overwritten,
+    Merged,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub file: String,
+    pub date: Option<String>,
+    pub status: ImportStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ImportResult {
+    fn failed(file: &str, date: Option<Date>, message: impl Into<String>) -> Self {
+        ImportResult {
+            file: file.to_string(),
+            date: date.map(|date| date.to_string()),
+            status: ImportStatus::Failed,
+            message: Some(message.into()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ImportedEntry {
+    /// several if entries of the same day were combined, e.g. from day one
+    pub files: Vec<String>,
+    pub date: Date,
+    pub content: TiptapJsonContent,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedImport {
+    pub entries: Vec<ImportedEntry>,
+    /// files which couldn't be read or converted
+    pub failed: Vec<ImportResult>,
+}
+
+impl ParsedImport {
+    fn push(&mut self, file: &str, date: Date, mut content: TiptapJsonContent) {
+        strip_disallowed_links(&mut content);
+        match self.entries.iter_mut().find(|entry| entry.date == date) {
+            Some(entry) => {
+                entry.files.push(file.to_string());
+                let existing = std::mem::replace(&mut entry.content, empty_doc());
+                entry.content = merge_docs(existing, content);
+            }
+            None => self.entries.push(ImportedEntry {
+                files: vec![file.to_string()],
+                date,
+                content,
+            }),
+        }
+    }
+
+    fn fail(&mut self, file: &str, message: impl Into<String>) {
+        self.failed.push(ImportResult::failed(file, None, message));
+    }
+}
+
+/* --------------------------------- parsing -------------------------------- */
+
+pub async fn parse(body: &[u8]) -> Result<ParsedImport, ImportError> {
+    let mut parsed = ParsedImport::default();
+    if body.starts_with(ZIP_MAGIC) {
+        parse_zip(body.to_vec(), MAX_TOTAL_SIZE, &mut parsed).await?;
+    } else {
+        let json = serde_json::from_slice::<Value>(body)?;
+        if is_day_one(&json) {
+            parse_day_one(json, "body", &mut parsed);
+        } else if is_journey(&json) {
+            parse_journey(json, "body", &mut parsed);
+        } else {
+            return Err(ImportError::Invalid(
+                "expected a zip, a day one or a journey json export".to_string(),
+            ));
+        }
+    }
+    parsed.entries.sort_by_key(|entry| entry.date);
+    Ok(parsed)
+}
+
+struct Zip {
+    reader: ZipFileReader,
+    max_total_size: u64,
+    /// uncompressed, including files which failed
+    read_size: u64,
+}
+
+impl Zip {
+    /// fails the whole import once the files read so far are too large, so a
+    /// zip of many small bombs can't get through either
+    async fn read_file(&mut self, index: usize) -> Result<Result<Vec<u8>, String>, ImportError> {
+        let remaining = self.max_total_size.saturating_sub(self.read_size);
+        let limit = MAX_FILE_SIZE.min(remaining);
+        let reader = match self.reader.reader_with_entry(index).await {
+            Ok(reader) => reader,
+            Err(err) => return Ok(Err(err.to_string())),
+        };
+        let mut data = Vec::new();
+        let result = reader.take(limit + 1).read_to_end(&mut data).await;
+        self.read_size += data.len() as u64;
+        if let Err(err) = result {
+            return Ok(Err(err.to_string()));
+        }
+        if data.len() as u64 > limit {
+            if limit < MAX_FILE_SIZE {
+                return Err(ImportError::TooLarge(self.max_total_size));
+            }
+            return Ok(Err(format!("larger than {} bytes", MAX_FILE_SIZE)));
+        }
+        Ok(Ok(data))
+    }
+
+    async fn read_text_file(
+        &mut self,
+        index: usize,
+    ) -> Result<Result<String, String>, ImportError> {
+        Ok(self
+            .read_file(index)
+            .await?
+            .and_then(|data| String::from_utf8(data).map_err(|_| "not utf-8".to_string())))
+    }
+}
+
+async fn parse_zip(
+    data: Vec<u8>,
+    max_total_size: u64,
+    parsed: &mut ParsedImport,
+) -> Result<(), ImportError> {
+    let mut zip = Zip {
+        reader: ZipFileReader::new(data).await?,
+        max_total_size,
+        read_size: 0,
+    };
+    let entries = zip.reader.file().entries();
+    if entries.len() > MAX_FILES {
+        return Err(ImportError::Invalid(format!(
+            "more than {} files",
+            MAX_FILES
+        )));
+    }
+
+    let mut files = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let Ok(filename) = entry.filename().as_str() else {
+            continue;
+        };
+        // e.g. `__MACOSX/` or `.DS_Store`
+        let is_hidden = filename
+            .split('/')
+            .any(|part| part.starts_with('.') || part == "__MACOSX");
+        if !entry.dir().unwrap_or(true) && !is_hidden {
+            files.insert(filename.to_string(), index);
+        }
+    }
+
+    if let Some(&index) = files.get(MANIFEST_FILENAME) {
+        return parse_export(&mut zip, index, &files, parsed).await;
+    }
+
+    let mut filenames = files.keys().cloned().collect::<Vec<_>>();
+    filenames.sort();
+    for filename in filenames {
+        let index = files[&filename];
+        let lowercase = filename.to_lowercase();
+        if lowercase.ends_with(".json") {
+            let json = zip.read_text_file(index).await?.and_then(|text| {
+                serde_json::from_str::<Value>(&text).map_err(|err| err.to_string())
+            });
+            match json {
+                Ok(json) if is_day_one(&json) => parse_day_one(json, &filename, parsed),
+                Ok(json) if is_journey(&json) => parse_journey(json, &filename, parsed),
+                Ok(_) => parsed.fail(&filename, "not a day one or journey export"),
+                Err(err) => parsed.fail(&filename, err),
+            }
+        } else if MARKDOWN_EXTENSIONS
+            .iter()
+            .any(|extension| lowercase.ends_with(extension))
+        {
+            let Some(date) = date_from_filename(&filename) else {
+                parsed.fail(&filename, "the filename doesn't start with a date");
+                continue;
+            };
+            match zip.read_text_file(index).await? {
+                Ok(markdown) => {
+                    parsed.push(&filename, date, TiptapJsonContent::from_markdown(&markdown))
+                }
+                Err(err) => parsed.fail(&filename, err),
+            }
+        }
+        // anything else, e.g. photos of day one exports, is ignored
+    }
+    Ok(())
+}
+
+/// e.g. `2024-01-31.md` or `journal/2024-01-31 trip.md`
+fn date_from_filename(filename: &str) -> Option<Date> {
+    let basename = filename.rsplit('/').next()?;
+    let date = basename.get(..10)?;
+    Date::parse(date, format_description!("[year]-[month]-[day]")).ok()
+}
+
+#[derive(Deserialize)]
+struct ExportManifest {
+    entries: Vec<ExportManifestEntry>,
+}
+
+#[derive(Deserialize)]
+struct ExportManifestEntry {
+    date: String,
+    json: String,
+    markdown: String,
+}
+
+/// the tiptap json is imported as is, the markdown only if the json is missing
+async fn parse_export(
+    zip: &mut Zip,
+    manifest_index: usize,
+    files: &HashMap<String, usize>,
+    parsed: &mut ParsedImport,
+) -> Result<(), ImportError> {
+    let manifest = zip
+        .read_text_file(manifest_index)
+        .await?
+        .map_err(ImportError::Invalid)?;
+    let manifest = serde_json::from_str::<ExportManifest>(&manifest)?;
+
+    for entry in manifest.entries {
+        let Ok(date) = Date::parse(&entry.date, format_description!("[year]-[month]-[day]")) else {
+            parsed.fail(MANIFEST_FILENAME, format!("invalid date `{}`", entry.date));
+            continue;
+        };
+        let content = if let Some(&index) = files.get(&entry.json) {
+            zip.read_text_file(index).await?.and_then(|json| {
+                serde_json::from_str::<TiptapJsonContent>(&json).map_err(|err| err.to_string())
+            })
+        } else if let Some(&index) = files.get(&entry.markdown) {
+            zip.read_text_file(index)
+                .await?
+                .map(|markdown| TiptapJsonContent::from_markdown(&markdown))
+        } else {
+            Err("missing in the zip".to_string())
+        };
+        match content {
+            Ok(content) => parsed.push(&entry.json, date, content),
+            Err(err) => parsed.fail(&entry.json, err),
+        }
+    }
+    Ok(())
+}
+
+/* --------------------------------- day one -------------------------------- */
+
+// ref: https://dayoneapp.com/guides/settings/importing-data-to-day-one/
+
+fn is_day_one(json: &Value) -> bool {
+    json.get("entries").is_some_and(Value::is_array)
+}
+
+#[derive(Deserialize)]
+struct DayOneEntry {
+    uuid: Option<String>,
+    #[serde(rename = "creationDate")]
+    creation_date: String,
+    #[serde(rename = "timeZone")]
+    time_zone: Option<String>,
+    #[serde(default)]
+    text: String,
+}
+
+fn parse_day_one(json: Value, file: &str, parsed: &mut ParsedImport) {
+    let Some(Value::Array(entries)) = json.get("entries").cloned() else {
+        return;
+    };
+    for (i, entry) in entries.into_iter().enumerate() {
+        let mut name = format!("{}#{}", file, i);
+        let entry = match serde_json::from_value::<DayOneEntry>(entry) {
+            Ok(entry) => entry,
+            Err(err) => {
+                parsed.fail(&name, err.to_string());
+                continue;
+            }
+        };
+        if let Some(uuid) = &entry.uuid {
+            name = format!("{}#{}", file, uuid);
+        }
+        match day_one_date(&entry.creation_date, entry.time_zone.as_deref()) {
+            Some(date) => parsed.push(&name, date, TiptapJsonContent::from_markdown(&entry.text)),
+            None => parsed.fail(&name, format!("invalid date `{}`", entry.creation_date)),
+        }
+    }
+}
+
+/// day one stores utc, but the entry belongs to the day where it was written
+fn day_one_date(creation_date: &str, time_zone: Option<&str>) -> Option<Date> {
+    let datetime = OffsetDateTime::parse(creation_date, &Rfc3339).ok()?;
+    Some(local_date(datetime, time_zone))
+}
+
+fn local_date(datetime: OffsetDateTime, time_zone: Option<&str>) -> Date {
+    match time_zone.and_then(timezones::get_by_name) {
+        Some(tz) => datetime.to_timezone(tz).date(),
+        None => datetime.date(),
+    }
+}
+
+/* --------------------------------- journey -------------------------------- */
+
+// journey exports a zip with a json file per entry, e.g. `1704164400000-abc.json`
+
+fn is_journey(json: &Value) -> bool {
+    json.get("date_journal").is_some_and(Value::is_number)
+}
+
+#[derive(Deserialize)]
+struct JourneyEntry {
+    /// unix timestamp in milliseconds
+    date_journal: i64,
+    timezone: Option<String>,
+    #[serde(default)]
+    text: String,
+    /// `markdown`, or `html` for entries of its newer editor
+    #[serde(rename = "type")]
+    type_: Option<String>,
+}
+
+fn parse_journey(json: Value, file: &str, parsed: &mut ParsedImport) {
+    let entry = match serde_json::from_value::<JourneyEntry>(json) {
+        Ok(entry) => entry,
+        Err(err) => return parsed.fail(file, err.to_string()),
+    };
+    // there is no html to tiptap conversion yet
+    if entry.type_.as_deref() == Some("html") {
+        return parsed.fail(file, "html journey entries are not supported");
+    }
+    let datetime =
+        OffsetDateTime::from_unix_timestamp_nanos(entry.date_journal as i128 * 1_000_000);
+    match datetime {
+        Ok(datetime) => {
+            let date = local_date(datetime, entry.timezone.as_deref());
+            parsed.push(file, date, TiptapJsonContent::from_markdown(&entry.text));
+        }
+        Err(_) => parsed.fail(file, format!("invalid date `{}`", entry.date_journal)),
+    }
+}
+
+/* --------------------------------- content -------------------------------- */
+
+fn empty_doc() -> TiptapJsonContent {
+    TiptapJsonContent::from_markdown("")
+}
+
+fn is_empty(doc: &TiptapJsonContent) -> bool {
+    doc.to_plain_text().trim().is_empty()
+}
+
+/// `a`, a horizontal rule and `b`
+pub fn merge_docs(a: TiptapJsonContent, b: TiptapJsonContent) -> TiptapJsonContent {
+    if is_empty(&a) {
+        return b;
+    }
+    if is_empty(&b) {
+        return a;
+    }
+    let mut merged = a;
+    let content = merged.content.get_or_insert_with(Vec::new);
+    content.extend(
+        TiptapJsonContent::from_markdown("---")
+            .content
+            .unwrap_or_default(),
+    );
+    content.extend(b.content.unwrap_or_default());
+    merged
+}
+
+/// e.g. `dayone-moment://` photos, which the editor would reject. their text
+/// is kept.
+fn strip_disallowed_links(jc: &mut TiptapJsonContent) {
+    if let Some(marks) = &mut jc.marks {
+        marks.retain(|mark: &TiptapMark| {
+            mark.type_ != "link"
+                || mark
+                    .attrs
+                    .as_ref()
+                    .and_then(|attrs| attrs.get("href"))
+                    .and_then(Value::as_str)
+                    .is_some_and(html::is_allowed_href)
+        });
+    }
+    for child in jc.content.iter_mut().flatten() {
+        strip_disallowed_links(child);
+    }
+}
+
+/* --------------------------------- saving --------------------------------- */
+
+pub async fn import_entries(
+    repo: &dyn Repository,
+    user_id: i64,
+    entries: Vec<ImportedEntry>,
+    strategy: ConflictStrategy,
+) -> Result<Vec<ImportResult>, sqlx::Error> {
+    let mut results = Vec::new();
+    for entry in entries {
+        let file = entry.files.join(", ");
+        let result = match import_entry(repo, user_id, entry.date, entry.content, strategy).await? {
+            Ok(status) => ImportResult {
+                file,
+                date: Some(entry.date.to_string()),
+                status,
+                message: None,
+            },
+            Err(message) => ImportResult::failed(&file, Some(entry.date), message),
+        };
+        results.push(result);
+    }
+    Ok(results)
+}
+
+async fn import_entry(
+    repo: &dyn Repository,
+    user_id: i64,
+    date: Date,
+    mut content: TiptapJsonContent,
+    strategy: ConflictStrategy,
+) -> Result<Result<ImportStatus, String>, sqlx::Error> {
+    let existing_entry = repo.get_entry_by_user_and_date(user_id, date).await?;
+
+    let status = match (&existing_entry, strategy) {
+        (None, _) => ImportStatus::Created,
+        (Some(_), ConflictStrategy::Skip) => return Ok(Ok(ImportStatus::Skipped)),
+        (Some(_), ConflictStrategy::Overwrite) => ImportStatus::Overwritten,
+        (Some(entry), ConflictStrategy::Merge) => {
+            let Ok(existing) = serde_json::from_value::<TiptapJsonContent>(entry.text.clone())
+            else {
+                return Ok(Err("the existing entry can't be read".to_string()));
+            };
+            content = merge_docs(existing, content);
+            ImportStatus::Merged
+        }
+    };
+
+    if let Err(errors) = content.validate() {
+        let message = errors
+            .first()
+            .map(|error| format!("{} at `{}`", error.message, error.path))
+            .unwrap_or_default();
+        return Ok(Err(message));
+    }
+    let Ok(text) = EntryText::new(&content) else {
+        return Ok(Err("the entry can't be saved".to_string()));
+    };
+
+    let revision = match existing_entry {
+        None => repo.create_entry(user_id, date, text).await?,
+        // the previous text is always kept as a revision
+        Some(entry) => {
+            repo.update_entry_text_by_user_and_date(
+                text,
+                user_id,
+                date,
+                Some(entry.revision),
+                time::Duration::ZERO,
+            )
+            .await?
+        }
+    };
+    match revision {
+        Some(_) => Ok(Ok(status)),
+        None => Ok(Err("the entry was changed during the import".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_zip::{Compression, ZipEntryBuilder, tokio::write::ZipFileWriter};
+    use serde_json::json;
+    use time::macros::date;
+
+    use super::*;
+
+    async fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = ZipFileWriter::with_tokio(Vec::new());
+        for (filename, data) in files {
+            let entry = ZipEntryBuilder::new(filename.to_string().into(), Compression::Deflate);
+            zip.write_entry_whole(entry, data.as_bytes()).await.unwrap();
+        }
+        zip.close().await.unwrap().into_inner()
+    }
+
+    fn markdown(entry: &ImportedEntry) -> String {
+        entry.content.to_markdown()
+    }
+
+    #[tokio::test]
+    async fn markdown_zip() {
+        let data = zip(&[
+            ("journal/2024-01-31 trip.md", "# Trip\n\n**bold**"),
+            ("2024-01-01.txt", "first"),
+            ("notes.md", "no date"),
+            ("__MACOSX/2024-01-02.md", "ignored"),
+            ("photo.jpg", "ignored"),
+        ])
+        .await;
+        let parsed = parse(&data).await.unwrap();
+
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[0].date, date!(2024 - 01 - 01));
+        assert_eq!(markdown(&parsed.entries[0]), "first");
+        assert_eq!(parsed.entries[1].date, date!(2024 - 01 - 31));
+        assert_eq!(markdown(&parsed.entries[1]), "# Trip\n\n**bold**");
+
+        assert_eq!(parsed.failed.len(), 1);
+        assert_eq!(parsed.failed[0].file, "notes.md");
+    }
+
+    #[tokio::test]
+    async fn day_one_json() {
+        let export = json!({
+            "metadata": { "version": "1.0" },
+            "entries": [
+                // the evening of the 1st in new york
+                { "uuid": "A", "creationDate": "2024-01-02T03:00:00Z", "timeZone": "America/New_York", "text": "one" },
+                { "uuid": "B", "creationDate": "2024-01-01T12:00:00Z", "timeZone": "America/New_York", "text": "two ![](dayone-moment://photo)" },
+                { "uuid": "C", "creationDate": "2024-01-02T03:00:00Z", "text": "three" },
+                { "uuid": "D", "creationDate": "yesterday", "text": "invalid" },
+            ]
+        });
+        let parsed = parse(export.to_string().as_bytes()).await.unwrap();
+
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[0].date, date!(2024 - 01 - 01));
+        assert_eq!(parsed.entries[0].files, ["body#A", "body#B"]);
+        // the photo link is stripped, its (empty) text kept
+        assert_eq!(markdown(&parsed.entries[0]), "one\n\n---\n\ntwo ");
+        assert_eq!(parsed.entries[1].date, date!(2024 - 01 - 02));
+
+        assert_eq!(parsed.failed.len(), 1);
+        assert_eq!(parsed.failed[0].file, "body#D");
+    }
+
+    #[tokio::test]
+    async fn export_zip() {
+        let manifest = json!({
+            "version": 1,
+            "entries": [
+                { "date": "2024-01-01", "json": "entries/2024-01-01.json", "markdown": "entries/2024-01-01.md" },
+                { "date": "2024-01-02", "json": "entries/2024-01-02.json", "markdown": "entries/2024-01-02.md" },
+                { "date": "2024-01-03", "json": "entries/2024-01-03.json", "markdown": "entries/2024-01-03.md" },
+            ]
+        })
+        .to_string();
+        let doc = json!({ "type": "doc", "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "json" }] }] }).to_string();
+        let data = zip(&[
+            ("manifest.json", &manifest),
+            ("entries/2024-01-01.json", &doc),
+            ("entries/2024-01-01.md", "markdown"),
+            ("entries/2024-01-02.md", "markdown"),
+        ])
+        .await;
+        let parsed = parse(&data).await.unwrap();
+
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(markdown(&parsed.entries[0]), "json");
+        assert_eq!(markdown(&parsed.entries[1]), "markdown");
+        assert_eq!(parsed.failed.len(), 1);
+        assert_eq!(parsed.failed[0].file, "entries/2024-01-03.json");
+    }
+
+    #[tokio::test]
+    async fn journey_zip() {
+        let entry = |date_journal: i64, type_: &str, text: &str| {
+            json!({ "id": "x", "date_journal": date_journal, "timezone": "America/New_York", "type": type_, "text": text }).to_string()
+        };
+        let data = zip(&[
+            // the evening of the 1st in new york
+            (
+                "1704164400000-a.json",
+                &entry(1704164400000, "markdown", "*one*"),
+            ),
+            (
+                "1704201600000-b.json",
+                &entry(1704201600000, "html", "<p>two</p>"),
+            ),
+        ])
+        .await;
+        let parsed = parse(&data).await.unwrap();
+
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].date, date!(2024 - 01 - 01));
+        assert_eq!(markdown(&parsed.entries[0]), "*one*");
+        assert_eq!(parsed.failed.len(), 1);
+        assert_eq!(parsed.failed[0].file, "1704201600000-b.json");
+
+        let parsed = parse(entry(1704164400000, "markdown", "one").as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(parsed.entries[0].files, ["body"]);
+    }
+
+    #[tokio::test]
+    async fn limits_total_size() {
+        let data = zip(&[("2024-01-01.md", "first"), ("2024-01-02.md", "second")]).await;
+        let mut parsed = ParsedImport::default();
+        let err = parse_zip(data.clone(), 10, &mut parsed).await.unwrap_err();
+        assert!(matches!(err, ImportError::TooLarge(10)));
+
+        let mut parsed = ParsedImport::default();
+        parse_zip(data, 11, &mut parsed).await.unwrap();
+        assert_eq!(parsed.entries.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn rejects_other_files() {
+        assert!(parse(b"not json").await.is_err());
+        assert!(parse(b"{\"foo\": 1}").await.is_err());
+        assert!(parse(b"PK\x03\x04broken").await.is_err());
+    }
+
+    #[test]
+    fn merges_docs() {
+        let merged = merge_docs(
+            TiptapJsonContent::from_markdown("a"),
+            TiptapJsonContent::from_markdown("b"),
+        );
+        assert_eq!(merged.to_markdown(), "a\n\n---\n\nb");
+
+        let merged = merge_docs(empty_doc(), TiptapJsonContent::from_markdown("b"));
+        assert_eq!(merged.to_markdown(), "b");
+    }
+}
@@ -2,21 +2,38 @@
 // allow in tests
 #![cfg_attr(test, allow(clippy::unwrap_used, clippy::panic))]
 
+mod backfill;
 mod controller;
 mod datetime;
 mod db;
 mod env;
+mod export;
 mod extractor;
+mod hasher;
+mod html;
+mod import;
+mod mailer;
+mod markdown;
 mod middleware;
+mod migrate;
+mod rate_limit;
+mod schema;
+mod search;
+mod state;
+mod stats;
+mod streak;
 mod tiptap;
+mod totp;
+mod trash;
 mod utils;
-use crate::env::Env;
+use crate::{env::Env, state::AppState};
 use axum::{
     Router,
-    routing::{delete, get, post, put},
+    extract::DefaultBodyLimit,
+    routing::{delete, get, patch, post, put},
 };
 use log::info;
-use sqlx::SqlitePool;
+use std::net::SocketAddr;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
 #[tokio::main]
@@ -28,25 +45,129 @@ async fn main() {
     // ref: https://github.com/tokio-rs/tracing/blob/tracing-subscriber-0.3.19/README.md
     tracing_subscriber::fmt::init();
 
-    let pool = SqlitePool::connect(&Env::get().database_url)
+    migrate::run(&Env::get().database_url)
+        .await
+        .expect("Failed to migrate the database");
+
+    let repo = db::connect(&Env::get().database_url)
         .await
         .expect("Failed to connect to the database");
 
+    // one-off commands, e.g. `server backfill-word-count`
+    if let Some(command) = std::env::args().nth(1) {
+        match command.as_str() {
+            // the migrations are applied above, e.g. before starting a new
+            // version of the server
+            "migrate" => println!("Migrated the database"),
+            "backfill-word-count" => {
+                let updated = backfill::word_counts(&*repo)
+                    .await
+                    .expect("Failed to recompute the word counts");
+                println!("Recomputed the word count of {} rows", updated);
+            }
+            _ => {
+                eprintln!(
+                    "Unknown command `{}`, expected `migrate` or `backfill-word-count`",
+                    command
+                );
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
+    let mailer = mailer::from_env().expect("Failed to set up the mailer");
+    let rate_limiter = rate_limit::from_env(repo.clone());
+    let hasher = hasher::Hasher::from_env().expect("Failed to set up the password hasher");
+    trash::spawn_purge_task(repo.clone());
+    search::spawn_index_task(repo.clone());
+
+    let auth_routes = Router::new()
+        .route("/api/auth/signup", post(controller::signup))
+        .route(
+            "/api/auth/login",
+            post(controller::login).layer(axum::middleware::from_fn_with_state(
+                rate_limiter.clone(),
+                middleware::login_lockout,
+            )),
+        )
+        .route("/api/auth/login/totp", post(controller::login_totp))
+        .route("/api/auth/refresh", post(controller::refresh))
+        .route(
+            "/api/auth/forgot-password",
+            post(controller::forgot_password),
+        )
+        .route("/api/auth/reset-password", post(controller::reset_password))
+        .layer(axum::middleware::from_fn_with_state(
+            rate_limiter.clone(),
+            middleware::rate_limit,
+        ));
+
     let public_routes = Router::new()
         .route("/api", get(controller::root))
-        .route("/api/auth/signup", post(controller::signup))
-        .route("/api/auth/login", post(controller::login));
+        .merge(auth_routes);
 
     let protected_routes = Router::new()
+        .route("/api/auth/logout", post(controller::logout))
+        .route("/api/auth/logout-all", post(controller::logout_all))
+        .route("/api/me", get(controller::get_me))
+        .route("/api/me", patch(controller::update_me))
+        .route("/api/me", delete(controller::delete_me))
+        .route("/api/me/totp/setup", post(controller::setup_totp))
+        .route("/api/me/totp/enable", post(controller::enable_totp))
+        .route(
+            "/api/me/totp/recovery-codes",
+            post(controller::regenerate_recovery_codes),
+        )
+        .route("/api/me/totp", delete(controller::disable_totp))
+        .route("/api/stats", get(controller::get_stats))
+        .route("/api/streak", get(controller::get_streak))
+        .route("/api/export", get(controller::export_entries))
+        .route(
+            "/api/import",
+            post(controller::import_entries).layer(DefaultBodyLimit::max(import::MAX_IMPORT_SIZE)),
+        )
+        .route("/api/entry", get(controller::list_entries))
         .route("/api/entry/{date}", put(controller::put_entry))
         .route("/api/entry/dates", get(controller::get_all_entry_dates))
+        .route("/api/entry/today", get(controller::get_today_entry))
+        .route("/api/entry/search", get(controller::search_entries))
+        .route("/api/entry/trash", get(controller::list_trashed_entries))
+        .route(
+            "/api/entry/trash",
+            delete(controller::purge_all_trashed_entries),
+        )
+        .route(
+            "/api/entry/trash/{date}",
+            delete(controller::purge_trashed_entry),
+        )
+        .route(
+            "/api/entry/trash/{date}/restore",
+            post(controller::restore_trashed_entry),
+        )
         .route("/api/entry/{date}", get(controller::get_entry_by_date))
         .route(
             "/api/entry/{date}",
             delete(controller::delete_entry_by_date),
         )
+        .route(
+            "/api/entry/{date}/revisions",
+            get(controller::list_entry_revisions),
+        )
+        .route(
+            "/api/entry/{date}/revisions/{id}",
+            get(controller::get_entry_revision),
+        )
+        .route(
+            "/api/entry/{date}/revisions/{id}/diff",
+            get(controller::diff_entry_revision),
+        )
+        .route(
+            "/api/entry/{date}/revisions/{id}/restore",
+            post(controller::restore_entry_revision),
+        )
         .layer(axum::middleware::from_fn_with_state(
-            pool.clone(),
+            repo.clone(),
             middleware::authenticate,
         ));
 
@@ -54,13 +175,25 @@ async fn main() {
     let cors = CorsLayer::new()
         .allow_methods(tower_http::cors::Any)
         .allow_headers(tower_http::cors::Any)
-        .allow_origin(tower_http::cors::Any);
+        .allow_origin(tower_http::cors::Any)
+        // the client needs the `ETag` of an entry for `If-Match`, the
+        // filename of exports and the date of today's entry
+        .expose_headers([
+            axum::http::header::ETAG,
+            axum::http::header::CONTENT_DISPOSITION,
+            axum::http::header::CONTENT_LOCATION,
+        ]);
 
     let app = public_routes
         .merge(protected_routes)
         // ref: https://github.com/tokio-rs/axum/blob/3b92cd7593a900d3c79c2aeb411f90be052a9a5c/examples/sqlx-postgres/src/main.rs#L55
         // ref: https://docs.rs/axum/0.8.4/axum/struct.Router.html#method.with_state
-        .with_state(pool)
+        .with_state(AppState {
+            repo,
+            mailer,
+            hasher,
+            rate_limiter,
+        })
         .layer(cors)
         // set up logging middleware
         // ref: https://docs.rs/axum/0.8.4/axum/struct.Router.html#example-3
@@ -85,7 +218,14 @@ async fn main() {
     }
 
     #[allow(clippy::panic)]
-    axum::serve(listener, app).await.unwrap_or_else(|err| {
+    // connect info is needed for rate limiting per ip
+    // ref: https://docs.rs/axum/0.8.4/axum/extract/struct.ConnectInfo.html
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap_or_else(|err| {
         panic!(
             "Failed to start server on {}:{}. Error: {}",
             host, port, err
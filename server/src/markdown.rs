@@ -0,0 +1,814 @@
+// ref: https://spec.commonmark.org/0.31.2/
+
+use std::collections::HashMap;
+
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use serde_json::{Value, json};
+
+use crate::{
+    schema,
+    tiptap::{TiptapJsonContent, TiptapMark},
+};
+
+/// between paragraphs, headings, etc.
+const BLOCK_SEPARATOR: &str = "\n\n";
+
+/* --------------------------- tiptap to markdown --------------------------- */
+
+impl TiptapJsonContent {
+    /// commonmark, with `~~strike~~` from gfm and `<u>underline</u>` as html,
+    /// since neither has a commonmark syntax
+    pub fn to_markdown(&self) -> String {
+        block_markdown(self, ListMarker::default())
+    }
+}
+
+/// consecutive lists alternate their markers, otherwise they would be parsed
+/// as a single list
+#[derive(Clone, Copy, Default)]
+struct ListMarker {
+    alternate: bool,
+}
+
+fn children(jc: &TiptapJsonContent) -> &[TiptapJsonContent] {
+    jc.content.as_deref().unwrap_or_default()
+}
+
+fn attr<'a>(jc: &'a TiptapJsonContent, name: &str) -> Option<&'a Value> {
+    jc.attrs.as_ref().and_then(|attrs| attrs.get(name))
+}
+
+fn blocks_markdown(jc: &TiptapJsonContent) -> String {
+    let mut blocks = Vec::new();
+    let mut previous_type = None;
+    let mut alternate = false;
+
+    for child in children(jc) {
+        let type_ = child.type_.as_deref();
+        alternate = type_ == previous_type && !alternate;
+        previous_type = type_;
+
+        let markdown = block_markdown(child, ListMarker { alternate });
+        // markdown can't express empty paragraphs
+        if !markdown.is_empty() {
+            blocks.push(markdown);
+        }
+    }
+    blocks.join(BLOCK_SEPARATOR)
+}
+
+fn block_markdown(jc: &TiptapJsonContent, marker: ListMarker) -> String {
+    match jc.type_.as_deref() {
+        Some("doc" | "listItem") => blocks_markdown(jc),
+        Some("paragraph") => inline_markdown(jc),
+        Some("heading") => {
+            let level = attr(jc, "level")
+                .and_then(Value::as_u64)
+                .unwrap_or(1)
+                .clamp(1, 6);
+            // headings are a single line
+            let text = inline_markdown(jc).replace("\\\n", " ");
+            format!("{} {}", "#".repeat(level as usize), text)
+        }
+        Some("blockquote") => prefix_lines(&blocks_markdown(jc), "> ", "> "),
+        Some("bulletList") => {
+            let bullet = if marker.alternate { "* " } else { "- " };
+            list_markdown(jc, |_| bullet.to_string())
+        }
+        Some("orderedList") => {
+            let start = attr(jc, "start").and_then(Value::as_u64).unwrap_or(1);
+            let delimiter = if marker.alternate { ')' } else { '.' };
+            list_markdown(jc, |i| format!("{}{} ", start + i as u64, delimiter))
+        }
+        Some("codeBlock") => {
+            let code = children(jc)
+                .iter()
+                .filter_map(|child| child.text.as_deref())
+                .collect::<String>();
+            let language = attr(jc, "language")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let fence = "`".repeat((longest_run(&code, '`') + 1).max(3));
+            format!("{}{}\n{}\n{}", fence, language, code, fence)
+        }
+        Some("horizontalRule") => "---".to_string(),
+        _ => match &jc.text {
+            Some(text) => escape_text(text, true),
+            None if children(jc).iter().all(|child| {
+                child.text.is_some() || child.type_.as_deref() == Some("hardBreak")
+            }) =>
+            {
+                inline_markdown(jc)
+            }
+            None => blocks_markdown(jc),
+        },
+    }
+}
+
+fn list_markdown(jc: &TiptapJsonContent, marker: impl Fn(usize) -> String) -> String {
+    let items = children(jc)
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let marker = marker(i);
+            // continuation lines must be indented to the content of the item
+            let indent = " ".repeat(marker.len());
+            prefix_lines(
+                &block_markdown(item, ListMarker::default()),
+                &marker,
+                &indent,
+            )
+        })
+        .collect::<Vec<_>>();
+    // items with several blocks make the list loose anyway
+    let separator = if items.iter().any(|item| item.contains(BLOCK_SEPARATOR)) {
+        BLOCK_SEPARATOR
+    } else {
+        "\n"
+    };
+    items.join(separator)
+}
+
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    text.split(|other| other != c)
+        .map(|run| run.len())
+        .max()
+        .unwrap_or_default()
+}
+
+/// outer marks first, so that e.g. a link stays open while its text changes
+/// from bold to normal. code is not a delimiter, see `code_span`.
+fn mark_rank(mark: &TiptapMark) -> usize {
+    match mark.type_.as_str() {
+        "link" => 0,
+        "bold" => 1,
+        "italic" => 2,
+        "strike" => 3,
+        "underline" => 4,
+        _ => 5,
+    }
+}
+
+fn same_mark(a: &TiptapMark, b: &TiptapMark) -> bool {
+    a.type_ == b.type_ && a.attrs == b.attrs
+}
+
+fn open_mark(mark: &TiptapMark) -> &'static str {
+    match mark.type_.as_str() {
+        "link" => "[",
+        "bold" => "**",
+        "italic" => "*",
+        "strike" => "~~",
+        "underline" => "<u>",
+        _ => "",
+    }
+}
+
+fn close_mark(mark: &TiptapMark) -> String {
+    match mark.type_.as_str() {
+        "link" => {
+            let href = mark
+                .attrs
+                .as_ref()
+                .and_then(|attrs| attrs.get("href"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            // ref: https://spec.commonmark.org/0.31.2/#link-destination
+            if href.contains([' ', '(', ')', '<', '>']) {
+                format!("](<{}>)", href.replace('<', "%3C").replace('>', "%3E"))
+            } else {
+                format!("]({})", href)
+            }
+        }
+        "bold" => "**".to_string(),
+        "italic" => "*".to_string(),
+        "strike" => "~~".to_string(),
+        "underline" => "</u>".to_string(),
+        _ => String::new(),
+    }
+}
+
+/// marks can span several text nodes, so they are only closed when the next
+/// node doesn't have them anymore
+fn inline_markdown(jc: &TiptapJsonContent) -> String {
+    let mut markdown = String::new();
+    let mut active: Vec<&TiptapMark> = Vec::new();
+
+    for child in children(jc) {
+        let Some(text) = &child.text else {
+            if child.type_.as_deref() == Some("hardBreak") {
+                markdown.push_str("\\\n");
+            } else {
+                markdown.push_str(&inline_markdown(child));
+            }
+            continue;
+        };
+
+        let all_marks = child.marks.as_deref().unwrap_or_default();
+        let is_code = all_marks.iter().any(|mark| mark.type_ == "code");
+        // whitespace alone doesn't need to change the marks
+        if !text.trim().is_empty() || is_code {
+            let mut marks = all_marks
+                .iter()
+                .filter(|mark| mark.type_ != "code")
+                .collect::<Vec<_>>();
+            marks.sort_by_key(|mark| mark_rank(mark));
+
+            let keep = active
+                .iter()
+                .zip(&marks)
+                .take_while(|(a, b)| same_mark(a, b))
+                .count();
+            close_marks(&mut markdown, &mut active, keep);
+
+            if keep < marks.len() {
+                // delimiters must not be followed by whitespace
+                let trimmed = text.trim_start();
+                markdown.push_str(&text[..text.len() - trimmed.len()]);
+                for mark in &marks[keep..] {
+                    markdown.push_str(open_mark(mark));
+                    active.push(mark);
+                }
+                push_text(&mut markdown, trimmed, is_code);
+                continue;
+            }
+        }
+        push_text(&mut markdown, text, is_code);
+    }
+
+    close_marks(&mut markdown, &mut active, 0);
+    markdown
+}
+
+fn push_text(markdown: &mut String, text: &str, is_code: bool) {
+    if is_code {
+        markdown.push_str(&code_span(text));
+    } else {
+        let line_start = markdown.is_empty() || markdown.ends_with('\n');
+        markdown.push_str(&escape_text(text, line_start));
+    }
+}
+
+fn close_marks(markdown: &mut String, active: &mut Vec<&TiptapMark>, keep: usize) {
+    if active.len() <= keep {
+        return;
+    }
+    // delimiters must not be preceded by whitespace either
+    let trailing = markdown.len() - markdown.trim_end_matches([' ', '\t']).len();
+    let whitespace = markdown.split_off(markdown.len() - trailing);
+    while active.len() > keep {
+        if let Some(mark) = active.pop() {
+            markdown.push_str(&close_mark(mark));
+        }
+    }
+    markdown.push_str(&whitespace);
+}
+
+/// ref: https://spec.commonmark.org/0.31.2/#code-spans
+fn code_span(code: &str) -> String {
+    let fence = "`".repeat(longest_run(code, '`') + 1);
+    let padding = if code.starts_with('`') || code.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{}{}{}{}{}", fence, padding, code, padding, fence)
+}
+
+/// backslash escapes everything which could be read as markdown syntax
+/// ref: https://spec.commonmark.org/0.31.2/#backslash-escapes
+fn escape_text(text: &str, line_start: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut at_line_start = line_start;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if at_line_start {
+            if matches!(c, '#' | '-' | '+' | '=') {
+                escaped.push('\\');
+            } else if c.is_ascii_digit() {
+                // ordered list markers like `1.` or `1)`
+                escaped.push(c);
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    escaped.push(digit);
+                }
+                if chars.peek().is_some_and(|c| matches!(c, '.' | ')')) {
+                    escaped.push('\\');
+                }
+                at_line_start = false;
+                continue;
+            }
+        }
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '~' | '&' | '!' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\\n"),
+            c => escaped.push(c),
+        }
+        at_line_start = c == '\n';
+    }
+    escaped
+}
+
+/* --------------------------- markdown to tiptap --------------------------- */
+
+impl TiptapJsonContent {
+    /// the inverse of `to_markdown`, other html than `<u>` and `<br>` is kept
+    /// as text
+    pub fn from_markdown(markdown: &str) -> Self {
+        let mut builder = Builder::new();
+        for event in Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH) {
+            builder.event(event);
+        }
+        builder.finish()
+    }
+}
+
+fn node(type_: &str, attrs: Option<HashMap<String, Value>>) -> TiptapJsonContent {
+    TiptapJsonContent {
+        type_: Some(type_.to_string()),
+        attrs,
+        content: None,
+        marks: None,
+        text: None,
+        extra: HashMap::new(),
+    }
+}
+
+fn mark(type_: &str, attrs: Option<HashMap<String, Value>>) -> TiptapMark {
+    TiptapMark {
+        type_: type_.to_string(),
+        attrs,
+        extra: HashMap::new(),
+    }
+}
+
+fn attrs(value: Value) -> Option<HashMap<String, Value>> {
+    serde_json::from_value(value).ok()
+}
+
+/// the defaults of the client's link extension
+/// ref: https://github.com/ueberdosis/tiptap/blob/v3.2.0/packages/extension-link/src/link.ts
+fn link_mark(href: &str) -> TiptapMark {
+    mark(
+        "link",
+        attrs(json!({
+            "href": href,
+            "target": "_blank",
+            "rel": "noopener noreferrer nofollow",
+            "class": null,
+        })),
+    )
+}
+
+fn push_child(parent: &mut TiptapJsonContent, child: TiptapJsonContent) {
+    parent.content.get_or_insert_with(Vec::new).push(child);
+}
+
+struct Builder {
+    /// open nodes, the first one is the doc
+    stack: Vec<TiptapJsonContent>,
+    marks: Vec<TiptapMark>,
+    /// tight list items contain text without a paragraph, but tiptap needs one
+    implicit_paragraph: bool,
+    /// nodes nested deeper than the schema allows, which are not opened
+    skipped: usize,
+}
+
+impl Builder {
+    fn new() -> Self {
+        Builder {
+            stack: vec![node("doc", None)],
+            marks: Vec::new(),
+            implicit_paragraph: false,
+            skipped: 0,
+        }
+    }
+
+    fn top(&mut self) -> &mut TiptapJsonContent {
+        let last = self.stack.len() - 1;
+        &mut self.stack[last]
+    }
+
+    fn open(&mut self, jc: TiptapJsonContent) {
+        self.close_implicit_paragraph();
+        // deeper documents are rejected by the schema anyway, and e.g. 100k
+        // nested blockquotes would overflow the stack when dropped
+        if self.stack.len() > schema::MAX_DEPTH {
+            self.skipped += 1;
+            return;
+        }
+        self.stack.push(jc);
+    }
+
+    fn close(&mut self) {
+        self.close_implicit_paragraph();
+        if self.skipped > 0 {
+            self.skipped -= 1;
+            return;
+        }
+        self.pop();
+    }
+
+    fn pop(&mut self) {
+        // the doc is never popped
+        if self.stack.len() < 2 {
+            return;
+        }
+        if let Some(mut jc) = self.stack.pop() {
+            if jc.type_.as_deref() == Some("codeBlock") {
+                // the code is followed by a line break before the fence
+                if let Some(text) = jc
+                    .content
+                    .as_mut()
+                    .and_then(|content| content.last_mut())
+                    .and_then(|text| text.text.as_mut())
+                    && text.ends_with('\n')
+                {
+                    text.pop();
+                }
+            }
+            push_child(self.top(), jc);
+        }
+    }
+
+    fn close_implicit_paragraph(&mut self) {
+        if self.implicit_paragraph {
+            self.implicit_paragraph = false;
+            self.pop();
+        }
+    }
+
+    fn push_inline(&mut self, jc: TiptapJsonContent) {
+        let in_textblock = matches!(
+            self.top().type_.as_deref(),
+            Some("paragraph" | "heading" | "codeBlock")
+        );
+        if !in_textblock {
+            self.stack.push(node("paragraph", None));
+            self.implicit_paragraph = true;
+        }
+        push_child(self.top(), jc);
+    }
+
+    fn push_text(&mut self, text: &str, extra_mark: Option<TiptapMark>) {
+        if text.is_empty() {
+            return;
+        }
+        let mut marks = self
+            .marks
+            .iter()
+            .map(|mark| TiptapMark {
+                type_: mark.type_.clone(),
+                attrs: mark.attrs.clone(),
+                extra: HashMap::new(),
+            })
+            .collect::<Vec<_>>();
+        marks.extend(extra_mark);
+
+        // the parser splits text at escapes, entities, etc.
+        if let Some(last) = self.top().content.as_mut().and_then(|c| c.last_mut())
+            && let Some(last_text) = last.text.as_mut()
+        {
+            let last_marks = last.marks.as_deref().unwrap_or_default();
+            if last_marks.len() == marks.len()
+                && last_marks.iter().zip(&marks).all(|(a, b)| same_mark(a, b))
+            {
+                last_text.push_str(text);
+                return;
+            }
+        }
+
+        let mut jc = node("text", None);
+        jc.text = Some(text.to_string());
+        jc.marks = (!marks.is_empty()).then_some(marks);
+        self.push_inline(jc);
+    }
+
+    fn pop_mark(&mut self, type_: &str) {
+        if let Some(index) = self.marks.iter().rposition(|mark| mark.type_ == type_) {
+            self.marks.remove(index);
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if self.top().type_.as_deref() == Some("codeBlock") {
+                    // code blocks contain a single text node, without marks
+                    let mut jc = node("text", None);
+                    jc.text = Some(text.to_string());
+                    match self.top().content.as_mut().and_then(|c| c.last_mut()) {
+                        Some(last) => last.text.get_or_insert_default().push_str(&text),
+                        None => push_child(self.top(), jc),
+                    }
+                } else {
+                    self.push_text(&text, None);
+                }
+            }
+            Event::Code(code) => self.push_text(&code, Some(mark("code", None))),
+            Event::SoftBreak => self.push_text(" ", None),
+            Event::HardBreak => self.push_inline(node("hardBreak", None)),
+            Event::Rule => {
+                self.close_implicit_paragraph();
+                push_child(self.top(), node("horizontalRule", None));
+            }
+            Event::InlineHtml(html) => match html.trim().to_ascii_lowercase().as_str() {
+                "<u>" => self.marks.push(mark("underline", None)),
+                "</u>" => self.pop_mark("underline"),
+                "<br>" | "<br/>" | "<br />" => self.push_inline(node("hardBreak", None)),
+                _ => self.push_text(&html, None),
+            },
+            Event::Html(html) => self.push_text(html.trim_end_matches('\n'), None),
+            // not enabled in the parser options
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph | Tag::HtmlBlock => self.open(node("paragraph", None)),
+            Tag::Heading { level, .. } => {
+                self.open(node("heading", attrs(json!({ "level": level as u8 }))))
+            }
+            Tag::BlockQuote(_) => self.open(node("blockquote", None)),
+            Tag::CodeBlock(kind) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .map(|language| language.to_string()),
+                    CodeBlockKind::Indented => None,
+                };
+                self.open(node("codeBlock", attrs(json!({ "language": language }))));
+            }
+            Tag::List(Some(start)) => {
+                self.open(node("orderedList", attrs(json!({ "start": start }))))
+            }
+            Tag::List(None) => self.open(node("bulletList", None)),
+            Tag::Item => self.open(node("listItem", None)),
+            Tag::Emphasis => self.marks.push(mark("italic", None)),
+            Tag::Strong => self.marks.push(mark("bold", None)),
+            Tag::Strikethrough => self.marks.push(mark("strike", None)),
+            // the client has no images, so they become links with the alt text
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                self.marks.push(link_mark(&dest_url))
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph
+            | TagEnd::HtmlBlock
+            | TagEnd::Heading(_)
+            | TagEnd::BlockQuote(_)
+            | TagEnd::CodeBlock
+            | TagEnd::List(_)
+            | TagEnd::Item => self.close(),
+            TagEnd::Emphasis => self.pop_mark("italic"),
+            TagEnd::Strong => self.pop_mark("bold"),
+            TagEnd::Strikethrough => self.pop_mark("strike"),
+            TagEnd::Link | TagEnd::Image => self.pop_mark("link"),
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> TiptapJsonContent {
+        self.close_implicit_paragraph();
+        while self.stack.len() > 1 {
+            self.pop();
+        }
+        let mut doc = self.stack.remove(0);
+        // the editor needs at least one block
+        if doc
+            .content
+            .as_ref()
+            .is_none_or(|content| content.is_empty())
+        {
+            push_child(&mut doc, node("paragraph", None));
+        }
+        doc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(content: Value) -> TiptapJsonContent {
+        serde_json::from_value(json!({ "type": "doc", "content": content })).unwrap()
+    }
+
+    fn text(text: &str, marks: Value) -> Value {
+        json!({ "type": "text", "text": text, "marks": marks })
+    }
+
+    fn paragraph(content: Value) -> Value {
+        json!({ "type": "paragraph", "content": content })
+    }
+
+    /// tiptap -> markdown -> tiptap -> markdown must be stable
+    fn assert_roundtrip(doc: &TiptapJsonContent, expected: &str) {
+        let markdown = doc.to_markdown();
+        assert_eq!(markdown, expected);
+        let parsed = TiptapJsonContent::from_markdown(&markdown);
+        assert_eq!(parsed.to_markdown(), expected);
+        assert_eq!(parsed.to_plain_text(), doc.to_plain_text());
+    }
+
+    #[test]
+    fn headings_and_paragraphs() {
+        let doc = doc(json!([
+            { "type": "heading", "attrs": { "level": 2 }, "content": [text("Title", json!(null))] },
+            paragraph(json!([text("first", json!(null))])),
+            { "type": "paragraph" },
+            paragraph(json!([text("second", json!(null))])),
+        ]));
+        assert_roundtrip(&doc, "## Title\n\nfirst\n\nsecond");
+    }
+
+    #[test]
+    fn marks() {
+        let doc = doc(json!([paragraph(json!([
+            text("bold", json!([{ "type": "bold" }])),
+            text(" ", json!(null)),
+            text("italic", json!([{ "type": "italic" }])),
+            text(" ", json!(null)),
+            text("strike", json!([{ "type": "strike" }])),
+            text(" ", json!(null)),
+            text("underline", json!([{ "type": "underline" }])),
+            text(" ", json!(null)),
+            text("code", json!([{ "type": "code" }])),
+        ]))]));
+        assert_roundtrip(&doc, "**bold** *italic* ~~strike~~ <u>underline</u> `code`");
+    }
+
+    #[test]
+    fn marks_spanning_text_nodes() {
+        let link = json!({ "type": "link", "attrs": { "href": "https://example.com" } });
+        let doc = doc(json!([paragraph(json!([
+            text("a ", json!([link, { "type": "bold" }])),
+            text("link", json!([link])),
+            text(" and more", json!(null)),
+        ]))]));
+        assert_roundtrip(&doc, "[**a** link](https://example.com) and more");
+    }
+
+    #[test]
+    fn whitespace_outside_delimiters() {
+        let doc = doc(json!([paragraph(json!([
+            text("bold ", json!([{ "type": "bold" }])),
+            text("text", json!(null)),
+        ]))]));
+        assert_roundtrip(&doc, "**bold** text");
+    }
+
+    #[test]
+    fn escapes_text() {
+        let doc = doc(json!([
+            paragraph(json!([text(
+                "# not *a* heading_ [x](y) <b> & `c` ~~s~~",
+                json!(null)
+            )])),
+            paragraph(json!([text("1. not a list", json!(null))])),
+            paragraph(json!([text("- nor this", json!(null))])),
+        ]));
+        assert_roundtrip(
+            &doc,
+            "\\# not \\*a\\* heading\\_ \\[x\\](y) \\<b\\> \\& \\`c\\` \\~\\~s\\~\\~\n\n1\\. not a list\n\n\\- nor this",
+        );
+    }
+
+    #[test]
+    fn code_spans_with_backticks() {
+        let doc = doc(json!([paragraph(json!([text(
+            "`a` b",
+            json!([{ "type": "code" }])
+        )]))]));
+        assert_roundtrip(&doc, "`` `a` b ``");
+    }
+
+    #[test]
+    fn links() {
+        let doc = doc(json!([paragraph(json!([text(
+            "spaced",
+            json!([{ "type": "link", "attrs": { "href": "https://example.com/a b" } }])
+        )]))]));
+        assert_roundtrip(&doc, "[spaced](<https://example.com/a b>)");
+    }
+
+    #[test]
+    fn hard_breaks() {
+        let doc = doc(json!([paragraph(json!([
+            text("one", json!(null)),
+            { "type": "hardBreak" },
+            text("two", json!(null)),
+        ]))]));
+        assert_roundtrip(&doc, "one\\\ntwo");
+    }
+
+    #[test]
+    fn lists() {
+        let item = |content: Value| json!({ "type": "listItem", "content": content });
+        let doc = doc(json!([
+            {
+                "type": "bulletList",
+                "content": [
+                    item(json!([paragraph(json!([text("one", json!(null))]))])),
+                    item(json!([
+                        paragraph(json!([text("two", json!(null))])),
+                        {
+                            "type": "orderedList",
+                            "attrs": { "start": 3 },
+                            "content": [item(json!([paragraph(json!([text("three", json!(null))]))]))]
+                        },
+                    ])),
+                ]
+            },
+            {
+                "type": "bulletList",
+                "content": [item(json!([paragraph(json!([text("separate", json!(null))]))]))]
+            },
+        ]));
+        // a nested list makes the item, and so the list, loose
+        assert_roundtrip(&doc, "- one\n\n- two\n\n  3. three\n\n* separate");
+    }
+
+    #[test]
+    fn blockquotes() {
+        let doc = doc(json!([{
+            "type": "blockquote",
+            "content": [
+                paragraph(json!([text("quoted", json!(null))])),
+                paragraph(json!([text("twice", json!(null))])),
+            ]
+        }]));
+        assert_roundtrip(&doc, "> quoted\n>\n> twice");
+    }
+
+    #[test]
+    fn code_blocks() {
+        let doc = doc(json!([
+            {
+                "type": "codeBlock",
+                "attrs": { "language": "rust" },
+                "content": [text("fn main() {}\n\n```", json!(null))]
+            },
+            { "type": "horizontalRule" },
+        ]));
+        assert_roundtrip(&doc, "````rust\nfn main() {}\n\n```\n````\n\n---");
+    }
+
+    #[test]
+    fn parses_common_markdown() {
+        let parsed = TiptapJsonContent::from_markdown(
+            "Setext heading\n===\n\n    indented code\n\n* tight\n* list\n\nsoft\nbreak <u>u</u> ![alt](https://example.com/a.png)",
+        );
+        let json = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(json["content"][0]["type"], "heading");
+        assert_eq!(json["content"][0]["attrs"]["level"], 1);
+        assert_eq!(json["content"][1]["type"], "codeBlock");
+        assert_eq!(json["content"][1]["attrs"]["language"], Value::Null);
+        assert_eq!(json["content"][1]["content"][0]["text"], "indented code");
+        // tight list items have no paragraph in markdown
+        assert_eq!(
+            json["content"][2]["content"][0]["content"][0]["type"],
+            "paragraph"
+        );
+        assert_eq!(
+            parsed.to_markdown(),
+            "# Setext heading\n\n```\nindented code\n```\n\n- tight\n- list\n\nsoft break <u>u</u> [alt](https://example.com/a.png)"
+        );
+    }
+
+    #[test]
+    fn empty_markdown() {
+        let parsed = TiptapJsonContent::from_markdown("");
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap()["content"][0]["type"],
+            "paragraph"
+        );
+        assert_eq!(parsed.to_markdown(), "");
+    }
+}
@@ -1,21 +1,25 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
 use axum::{
-    extract::{Request, State},
+    body::Body,
+    extract::{ConnectInfo, Request, State},
     http::{
         StatusCode,
         header::{self},
     },
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
-use sqlx::SqlitePool;
+use serde::Deserialize;
 
 use crate::{
-    db::{self},
+    db::Repository,
+    rate_limit::{RateLimiter, TokenBucket},
     utils::decode_jwt,
 };
 
 pub async fn authenticate(
-    State(pool): State<SqlitePool>,
+    State(repo): State<Arc<dyn Repository>>,
     mut req: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
@@ -33,11 +37,144 @@ pub async fn authenticate(
     let jwt_data = decode_jwt(token).ok_or(StatusCode::UNAUTHORIZED)?;
     let user_id = jwt_data.claims.user_id;
 
-    let user = db::get_user_by_id(&pool, user_id)
+    // a valid signature is not enough, the session may have been logged out
+    let session = repo
+        .get_session_by_id(jwt_data.claims.session_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if session.user_id != user_id || !session.is_active() {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let user = repo
+        .get_user_by_id(user_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
     req.extensions_mut().insert(user);
+    req.extensions_mut().insert(session);
     Ok(next.run(req).await)
 }
+
+/* ------------------------------- rate limit ------------------------------- */
+
+// argon2 runs on every signup and login, so these also protect against cpu
+// exhaustion, not only brute force
+const IP_BUCKET: TokenBucket = TokenBucket::new(20, Duration::from_secs(6));
+const EMAIL_BUCKET: TokenBucket = TokenBucket::new(5, Duration::from_secs(12));
+// once empty, logins for the email are locked until a token refills
+const LOGIN_FAILURE_BUCKET: TokenBucket = TokenBucket::new(5, Duration::from_secs(15 * 60));
+// the same for wrong totp and recovery codes of a user, see `controller::login_totp`
+pub const TOTP_FAILURE_BUCKET: TokenBucket = TokenBucket::new(5, Duration::from_secs(15 * 60));
+
+const MAX_AUTH_BODY_SIZE: usize = 64 * 1024;
+
+pub fn too_many_requests(wait: Duration) -> Response {
+    let retry_after = (wait.as_secs_f64().ceil() as u64).max(1);
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after.to_string())],
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+struct EmailField {
+    email: Option<String>,
+}
+
+/// reads the `email` field of a json body, and puts the body back for the
+/// handler
+async fn extract_email(req: Request) -> Result<(Request, Option<String>), StatusCode> {
+    let (parts, body) = req.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_AUTH_BODY_SIZE)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+    let email = serde_json::from_slice::<EmailField>(&bytes)
+        .ok()
+        .and_then(|field| field.email)
+        .map(|email| email.trim().to_lowercase());
+    Ok((Request::from_parts(parts, Body::from(bytes)), email))
+}
+
+/// limits requests per client ip, and per email in the json body
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    // only missing when the app is not served with connect info, e.g. in tests
+    let addr = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    if let Some(addr) = addr {
+        let key = format!("ip:{}", addr.ip());
+        let wait = limiter
+            .take(&key, IP_BUCKET)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(wait) = wait {
+            return Ok(too_many_requests(wait));
+        }
+    }
+
+    let (req, email) = extract_email(req).await?;
+    if let Some(email) = email {
+        let key = format!("email:{}", email);
+        let wait = limiter
+            .take(&key, EMAIL_BUCKET)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(wait) = wait {
+            return Ok(too_many_requests(wait));
+        }
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// locks an email after repeated failed logins, regardless of whether an
+/// account exists for it
+pub async fn login_lockout(
+    State(limiter): State<RateLimiter>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let (req, email) = extract_email(req).await?;
+    let Some(email) = email else {
+        return Ok(next.run(req).await);
+    };
+
+    // taken before the attempt, so that concurrent attempts can't all pass
+    // the check before any failure is counted
+    let key = format!("login-failure:{}", email);
+    let wait = limiter
+        .take(&key, LOGIN_FAILURE_BUCKET)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if let Some(wait) = wait {
+        return Ok(too_many_requests(wait));
+    }
+
+    let response = next.run(req).await;
+    match response.status() {
+        StatusCode::UNAUTHORIZED => {}
+        status if status.is_success() => {
+            limiter
+                .reset(&key)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        }
+        // not a failed login, e.g. invalid input
+        _ => {
+            limiter
+                .give_back(&key, LOGIN_FAILURE_BUCKET)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        }
+    }
+    Ok(response)
+}
@@ -0,0 +1,278 @@
+// the schema is defined by the sql files in `migrations/`, and for postgres
+// in `migrations/postgres/`, which are embedded into the binary. sqlite
+// databases set up with `prisma migrate` before are adopted: their applied
+// migrations are checked against the embedded files and recorded as applied
+// for sqlx, so only newer migrations run.
+
+use std::str::FromStr;
+
+use log::info;
+use sha2::{Digest, Sha256};
+use sqlx::{
+    Connection, PgConnection, Postgres, SqliteConnection,
+    migrate::{Migrate, MigrateDatabase, MigrateError, Migrator},
+    sqlite::SqliteConnectOptions,
+};
+use thiserror::Error;
+
+use crate::db::Backend;
+
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!();
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("migrations/postgres");
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error(transparent)]
+    Db(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    Migrate(#[from] MigrateError),
+
+    #[error("migration `{0}` was applied by prisma but is missing from `migrations/`")]
+    UnknownPrismaMigration(String),
+
+    #[error("migration `{0}` was changed after prisma applied it")]
+    PrismaChecksumMismatch(String),
+
+    #[error("migration `{0}` failed when prisma applied it, resolve it before migrating")]
+    UnfinishedPrismaMigration(String),
+
+    #[error("{0} rows violate foreign keys after migrating")]
+    ForeignKeyViolations(usize),
+}
+
+/// creates the database if needed and applies the pending migrations
+pub async fn run(database_url: &str) -> Result<(), MigrationError> {
+    match Backend::from_url(database_url)? {
+        Backend::Sqlite => run_sqlite(database_url).await,
+        Backend::Postgres => run_postgres(database_url).await,
+    }
+}
+
+async fn run_postgres(database_url: &str) -> Result<(), MigrationError> {
+    if !Postgres::database_exists(database_url).await? {
+        Postgres::create_database(database_url).await?;
+    }
+    let mut conn = PgConnection::connect(database_url).await?;
+    POSTGRES_MIGRATOR.run(&mut conn).await?;
+    conn.close().await?;
+    Ok(())
+}
+
+async fn run_sqlite(database_url: &str) -> Result<(), MigrationError> {
+    // like `prisma migrate`, tables are redefined by copying them, which must
+    // not cascade to the rows referencing them. `PRAGMA foreign_keys` in the
+    // migrations is a no-op inside their transaction, so turn it off here and
+    // check the keys once all migrations are applied.
+    let options = SqliteConnectOptions::from_str(database_url)?
+        .create_if_missing(true)
+        .foreign_keys(false);
+    let mut conn = SqliteConnection::connect_with(&options).await?;
+
+    adopt_prisma_migrations(&mut conn).await?;
+    SQLITE_MIGRATOR.run(&mut conn).await?;
+
+    let violations = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(&mut conn)
+        .await?;
+    if !violations.is_empty() {
+        return Err(MigrationError::ForeignKeyViolations(violations.len()));
+    }
+
+    conn.close().await?;
+    Ok(())
+}
+
+/// records the migrations applied by prisma in `_sqlx_migrations`
+async fn adopt_prisma_migrations(conn: &mut SqliteConnection) -> Result<(), MigrationError> {
+    let has_prisma_migrations: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_prisma_migrations')",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if !has_prisma_migrations {
+        return Ok(());
+    }
+
+    // rolled back migrations are applied again, by prisma as well
+    let prisma_migrations: Vec<(String, String, bool)> = sqlx::query_as(
+        "SELECT migration_name, checksum, finished_at IS NOT NULL FROM _prisma_migrations WHERE rolled_back_at IS NULL ORDER BY started_at",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    conn.ensure_migrations_table().await?;
+    let applied = conn.list_applied_migrations().await?;
+
+    let mut adopted = 0;
+    let mut tx = conn.begin().await?;
+    for (name, checksum, finished) in prisma_migrations {
+        if !finished {
+            return Err(MigrationError::UnfinishedPrismaMigration(name));
+        }
+        let migration = SQLITE_MIGRATOR
+            .iter()
+            .find(|migration| {
+                format!(
+                    "{}_{}",
+                    migration.version,
+                    migration.description.replace(' ', "_")
+                ) == name
+            })
+            .ok_or_else(|| MigrationError::UnknownPrismaMigration(name.clone()))?;
+        if !prisma_checksum_matches(&migration.sql, &checksum) {
+            return Err(MigrationError::PrismaChecksumMismatch(name));
+        }
+        if applied
+            .iter()
+            .any(|applied| applied.version == migration.version)
+        {
+            continue;
+        }
+
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES (?, ?, TRUE, ?, 0)",
+        )
+        .bind(migration.version)
+        .bind(&*migration.description)
+        .bind(&*migration.checksum)
+        .execute(&mut *tx)
+        .await?;
+        adopted += 1;
+    }
+    tx.commit().await?;
+
+    if adopted > 0 {
+        info!("Adopted {} migrations applied by prisma", adopted);
+    }
+
+    Ok(())
+}
+
+/// prisma stores the sha256 of the file as it was on disk, which may have had
+/// windows line endings
+fn prisma_checksum_matches(sql: &str, checksum: &str) -> bool {
+    let sha256 = |sql: &str| format!("{:x}", Sha256::digest(sql));
+    checksum == sha256(sql) || checksum == sha256(&sql.replace('\n', "\r\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn memory_db() -> SqliteConnection {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .unwrap()
+            .foreign_keys(false);
+        SqliteConnection::connect_with(&options).await.unwrap()
+    }
+
+    /// a database as `prisma migrate` leaves it after the first `count` migrations
+    async fn prisma_db(count: usize) -> SqliteConnection {
+        let mut conn = memory_db().await;
+        sqlx::raw_sql(
+            r#"CREATE TABLE "_prisma_migrations" (
+                "id" TEXT PRIMARY KEY NOT NULL,
+                "checksum" TEXT NOT NULL,
+                "finished_at" DATETIME,
+                "migration_name" TEXT NOT NULL,
+                "logs" TEXT,
+                "rolled_back_at" DATETIME,
+                "started_at" DATETIME NOT NULL DEFAULT current_timestamp,
+                "applied_steps_count" INTEGER UNSIGNED NOT NULL DEFAULT 0
+            )"#,
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        for (i, migration) in SQLITE_MIGRATOR.iter().take(count).enumerate() {
+            sqlx::raw_sql(&migration.sql)
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            sqlx::query(
+                "INSERT INTO _prisma_migrations (id, checksum, finished_at, migration_name, started_at) VALUES (?, ?, 1, ?, ?)",
+            )
+            .bind(i.to_string())
+            .bind(format!("{:x}", Sha256::digest(&*migration.sql)))
+            .bind(format!(
+                "{}_{}",
+                migration.version,
+                migration.description.replace(' ', "_")
+            ))
+            .bind(i as i64)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        }
+        conn
+    }
+
+    async fn applied_versions(conn: &mut SqliteConnection) -> Vec<i64> {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(conn)
+            .await
+            .unwrap()
+    }
+
+    fn all_versions() -> Vec<i64> {
+        SQLITE_MIGRATOR
+            .iter()
+            .map(|migration| migration.version)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn migrates_empty_database() {
+        let mut conn = memory_db().await;
+        adopt_prisma_migrations(&mut conn).await.unwrap();
+        SQLITE_MIGRATOR.run(&mut conn).await.unwrap();
+        assert_eq!(applied_versions(&mut conn).await, all_versions());
+    }
+
+    #[tokio::test]
+    async fn adopts_prisma_migrations() {
+        let mut conn = prisma_db(3).await;
+        adopt_prisma_migrations(&mut conn).await.unwrap();
+        assert_eq!(applied_versions(&mut conn).await, all_versions()[..3]);
+
+        // adopting again is a no-op, the newer migrations are applied by sqlx
+        adopt_prisma_migrations(&mut conn).await.unwrap();
+        SQLITE_MIGRATOR.run(&mut conn).await.unwrap();
+        assert_eq!(applied_versions(&mut conn).await, all_versions());
+    }
+
+    #[tokio::test]
+    async fn rejects_changed_prisma_migrations() {
+        let mut conn = prisma_db(2).await;
+        sqlx::query("UPDATE _prisma_migrations SET checksum = 'changed' WHERE id = '1'")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        assert!(matches!(
+            adopt_prisma_migrations(&mut conn).await,
+            Err(MigrationError::PrismaChecksumMismatch(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_unfinished_prisma_migrations() {
+        let mut conn = prisma_db(2).await;
+        sqlx::query("UPDATE _prisma_migrations SET finished_at = NULL WHERE id = '1'")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        assert!(matches!(
+            adopt_prisma_migrations(&mut conn).await,
+            Err(MigrationError::UnfinishedPrismaMigration(_))
+        ));
+    }
+
+    #[test]
+    fn prisma_checksum_ignores_line_endings() {
+        let sql = "CREATE TABLE a (id INTEGER);\nCREATE TABLE b (id INTEGER);\n";
+        let crlf = format!("{:x}", Sha256::digest(sql.replace('\n', "\r\n")));
+        assert!(prisma_checksum_matches(sql, &crlf));
+        assert!(!prisma_checksum_matches(sql, "0"));
+    }
+}
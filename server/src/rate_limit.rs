@@ -0,0 +1,326 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+use crate::{
+    db::Repository,
+    env::{Env, RateLimitStoreKind},
+};
+
+// ref: https://en.wikipedia.org/wiki/Token_bucket
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucket {
+    pub capacity: f64,
+    /// tokens added per second
+    pub refill_rate: f64,
+}
+
+impl TokenBucket {
+    /// `capacity` requests at once, then one more every `interval`
+    pub const fn new(capacity: u32, interval: Duration) -> Self {
+        TokenBucket {
+            capacity: capacity as f64,
+            refill_rate: 1.0 / interval.as_secs_f64(),
+        }
+    }
+
+    fn refill(&self, state: Option<BucketState>, now: f64) -> BucketState {
+        match state {
+            Some(state) => BucketState {
+                tokens: (state.tokens + (now - state.updated_at).max(0.0) * self.refill_rate)
+                    .min(self.capacity),
+                updated_at: now,
+            },
+            // unknown keys start with a full bucket
+            None => BucketState {
+                tokens: self.capacity,
+                updated_at: now,
+            },
+        }
+    }
+
+    fn wait_time(&self, state: &BucketState) -> Option<Duration> {
+        if state.tokens >= 1.0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(
+            (1.0 - state.tokens) / self.refill_rate,
+        ))
+    }
+
+    /// returns the new state, and how long to wait if no token was available
+    pub fn take(&self, state: Option<BucketState>, now: f64) -> (BucketState, Option<Duration>) {
+        let mut state = self.refill(state, now);
+        let wait = self.wait_time(&state);
+        if wait.is_none() {
+            state.tokens -= 1.0;
+        }
+        (state, wait)
+    }
+
+    /// undoes `take`, never above the capacity
+    pub fn give_back(&self, state: Option<BucketState>, now: f64) -> BucketState {
+        let mut state = self.refill(state, now);
+        state.tokens = (state.tokens + 1.0).min(self.capacity);
+        state
+    }
+
+    /// like `take`, but without consuming a token
+    pub fn peek(&self, state: Option<BucketState>, now: f64) -> Option<Duration> {
+        self.wait_time(&self.refill(state, now))
+    }
+}
+
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct BucketState {
+    pub tokens: f64,
+    /// unix timestamp in seconds
+    pub updated_at: f64,
+}
+
+fn now() -> f64 {
+    OffsetDateTime::now_utc().unix_timestamp_nanos() as f64 / 1e9
+}
+
+/// `take` and `give_back` must be atomic, otherwise concurrent requests all
+/// see the same tokens and a burst gets through
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    async fn take(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+        now: f64,
+    ) -> Result<Option<Duration>, sqlx::Error>;
+    async fn give_back(&self, key: &str, bucket: TokenBucket, now: f64) -> Result<(), sqlx::Error>;
+    async fn remove(&self, key: &str) -> Result<(), sqlx::Error>;
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>) -> Self {
+        RateLimiter { store }
+    }
+
+    /// consumes a token, returns how long to wait if the bucket is empty
+    pub async fn take(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+    ) -> Result<Option<Duration>, sqlx::Error> {
+        self.store.take(key, bucket, now()).await
+    }
+
+    /// returns a token taken by `take`, e.g. if the request didn't count
+    pub async fn give_back(&self, key: &str, bucket: TokenBucket) -> Result<(), sqlx::Error> {
+        self.store.give_back(key, bucket, now()).await
+    }
+
+    pub async fn reset(&self, key: &str) -> Result<(), sqlx::Error> {
+        self.store.remove(key).await
+    }
+}
+
+pub fn from_env(repo: Arc<dyn Repository>) -> RateLimiter {
+    match Env::get().rate_limit_store {
+        RateLimitStoreKind::Memory => RateLimiter::new(Arc::new(MemoryStore::default())),
+        RateLimitStoreKind::Database => RateLimiter::new(Arc::new(DatabaseStore::new(repo))),
+    }
+}
+
+/* --------------------------------- memory --------------------------------- */
+
+/// buckets untouched for this long are full again for any sane config
+const MEMORY_STORE_MAX_IDLE_SECS: f64 = 24.0 * 60.0 * 60.0;
+const MEMORY_STORE_PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, BucketState>>,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn take(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+        now: f64,
+    ) -> Result<Option<Duration>, sqlx::Error> {
+        // held from reading the bucket until the new state is written
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        if buckets.len() >= MEMORY_STORE_PRUNE_THRESHOLD {
+            buckets.retain(|_, state| now - state.updated_at < MEMORY_STORE_MAX_IDLE_SECS);
+        }
+        let (state, wait) = bucket.take(buckets.get(key).copied(), now);
+        buckets.insert(key.to_string(), state);
+        Ok(wait)
+    }
+
+    async fn give_back(&self, key: &str, bucket: TokenBucket, now: f64) -> Result<(), sqlx::Error> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(state) = buckets.get_mut(key) {
+            *state = bucket.give_back(Some(*state), now);
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), sqlx::Error> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        buckets.remove(key);
+        Ok(())
+    }
+}
+
+/* -------------------------------- database -------------------------------- */
+
+/// survives restarts, and is shared by all server processes using the db
+pub struct DatabaseStore {
+    repo: Arc<dyn Repository>,
+}
+
+impl DatabaseStore {
+    pub fn new(repo: Arc<dyn Repository>) -> Self {
+        DatabaseStore { repo }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for DatabaseStore {
+    async fn take(
+        &self,
+        key: &str,
+        bucket: TokenBucket,
+        now: f64,
+    ) -> Result<Option<Duration>, sqlx::Error> {
+        if self
+            .repo
+            .take_rate_limit_token_by_key(key, bucket, now)
+            .await?
+        {
+            return Ok(None);
+        }
+        // only for the retry hint, so it doesn't matter if it changed since
+        let state = self.repo.get_rate_limit_bucket_by_key(key).await?;
+        Ok(Some(bucket.peek(state, now).unwrap_or(Duration::ZERO)))
+    }
+
+    async fn give_back(&self, key: &str, bucket: TokenBucket, now: f64) -> Result<(), sqlx::Error> {
+        self.repo
+            .return_rate_limit_token_by_key(key, bucket, now)
+            .await
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), sqlx::Error> {
+        self.repo.delete_rate_limit_bucket_by_key(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUCKET: TokenBucket = TokenBucket::new(2, Duration::from_secs(10));
+
+    #[test]
+    fn full_bucket_allows_burst() {
+        let (state, wait) = BUCKET.take(None, 0.0);
+        assert_eq!(wait, None);
+        let (state, wait) = BUCKET.take(Some(state), 0.0);
+        assert_eq!(wait, None);
+        let (_, wait) = BUCKET.take(Some(state), 0.0);
+        assert_eq!(wait, Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn empty_bucket_refills_over_time() {
+        let empty = BucketState {
+            tokens: 0.0,
+            updated_at: 0.0,
+        };
+        assert_eq!(BUCKET.peek(Some(empty), 5.0), Some(Duration::from_secs(5)));
+        assert_eq!(BUCKET.peek(Some(empty), 10.0), None);
+
+        // never more than capacity
+        let (state, _) = BUCKET.take(Some(empty), 1000.0);
+        assert_eq!(state.tokens, 1.0);
+    }
+
+    #[test]
+    fn peek_does_not_consume() {
+        let state = BucketState {
+            tokens: 1.0,
+            updated_at: 0.0,
+        };
+        assert_eq!(BUCKET.peek(Some(state), 0.0), None);
+        assert_eq!(BUCKET.peek(Some(state), 0.0), None);
+    }
+
+    #[tokio::test]
+    async fn memory_store_limits_and_resets() {
+        let limiter = RateLimiter::new(Arc::new(MemoryStore::default()));
+        assert_eq!(limiter.take("a", BUCKET).await.unwrap(), None);
+        assert_eq!(limiter.take("a", BUCKET).await.unwrap(), None);
+        assert!(limiter.take("a", BUCKET).await.unwrap().is_some());
+        // other keys are independent
+        assert_eq!(limiter.take("b", BUCKET).await.unwrap(), None);
+
+        limiter.reset("a").await.unwrap();
+        assert_eq!(limiter.take("a", BUCKET).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn memory_store_gives_back() {
+        let limiter = RateLimiter::new(Arc::new(MemoryStore::default()));
+        limiter.take("a", BUCKET).await.unwrap();
+        limiter.take("a", BUCKET).await.unwrap();
+        limiter.give_back("a", BUCKET).await.unwrap();
+        assert_eq!(limiter.take("a", BUCKET).await.unwrap(), None);
+        assert!(limiter.take("a", BUCKET).await.unwrap().is_some());
+
+        // never above the capacity
+        limiter.reset("a").await.unwrap();
+        limiter.take("a", BUCKET).await.unwrap();
+        limiter.give_back("a", BUCKET).await.unwrap();
+        limiter.give_back("a", BUCKET).await.unwrap();
+        assert_eq!(limiter.take("a", BUCKET).await.unwrap(), None);
+        assert_eq!(limiter.take("a", BUCKET).await.unwrap(), None);
+        assert!(limiter.take("a", BUCKET).await.unwrap().is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn concurrent_takes_share_the_burst() {
+        const BURST: TokenBucket = TokenBucket::new(5, Duration::from_secs(3600));
+        let limiter = RateLimiter::new(Arc::new(MemoryStore::default()));
+        let requests = 25;
+        let barrier = Arc::new(tokio::sync::Barrier::new(requests));
+        let tasks: Vec<_> = (0..requests)
+            .map(|_| {
+                let limiter = limiter.clone();
+                let barrier = barrier.clone();
+                tokio::spawn(async move {
+                    barrier.wait().await;
+                    limiter.take("a", BURST).await.unwrap().is_none()
+                })
+            })
+            .collect();
+
+        let mut taken = 0;
+        for task in tasks {
+            if task.await.unwrap() {
+                taken += 1;
+            }
+        }
+        assert_eq!(taken, 5);
+    }
+}
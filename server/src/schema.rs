@@ -0,0 +1,440 @@
+// the schema of the client's editor, see the extensions in
+// client/src/pages/writings/[date].tsx
+// ref: https://tiptap.dev/docs/editor/core-concepts/schema
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    html,
+    tiptap::{TiptapJsonContent, TiptapMark},
+};
+
+/// doc > list > item > list > item > ... leaves room for lists nested 14 deep
+pub const MAX_DEPTH: usize = 32;
+const MAX_NODES: usize = 50_000;
+/// in characters, a novel has about 500k
+const MAX_TEXT_LENGTH: usize = 1_000_000;
+/// the response lists only the first errors
+const MAX_ERRORS: usize = 20;
+
+#[derive(Debug, Serialize)]
+pub struct SchemaError {
+    /// json pointer to the node or mark, e.g. `/content/0/marks/1`
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ContentKind {
+    Blocks,
+    ListItems,
+    Inline,
+    /// text without marks
+    Code,
+    Empty,
+}
+
+struct NodeSpec {
+    content: ContentKind,
+    attrs: &'static [&'static str],
+}
+
+fn node_spec(type_: &str) -> Option<NodeSpec> {
+    let (content, attrs): (_, &'static [&'static str]) = match type_ {
+        "doc" | "blockquote" | "listItem" => (ContentKind::Blocks, &[]),
+        "bulletList" => (ContentKind::ListItems, &[]),
+        "orderedList" => (ContentKind::ListItems, &["start", "type"]),
+        "paragraph" => (ContentKind::Inline, &[]),
+        "heading" => (ContentKind::Inline, &["level"]),
+        "codeBlock" => (ContentKind::Code, &["language"]),
+        "horizontalRule" | "hardBreak" | "text" => (ContentKind::Empty, &[]),
+        _ => return None,
+    };
+    Some(NodeSpec { content, attrs })
+}
+
+fn is_block(type_: &str) -> bool {
+    matches!(
+        type_,
+        "paragraph"
+            | "heading"
+            | "blockquote"
+            | "bulletList"
+            | "orderedList"
+            | "codeBlock"
+            | "horizontalRule"
+    )
+}
+
+fn mark_attrs(type_: &str) -> Option<&'static [&'static str]> {
+    match type_ {
+        "bold" | "italic" | "strike" | "underline" | "code" => Some(&[]),
+        "link" => Some(&["href", "target", "rel", "class"]),
+        _ => None,
+    }
+}
+
+struct Validator {
+    errors: Vec<SchemaError>,
+    nodes: usize,
+    text_length: usize,
+}
+
+impl TiptapJsonContent {
+    /// checks the document against the schema of the client's editor. unknown
+    /// attrs and keys are stripped instead of rejected, since older clients
+    /// may have saved some.
+    pub fn validate(&mut self) -> Result<(), Vec<SchemaError>> {
+        let mut validator = Validator {
+            errors: Vec::new(),
+            nodes: 0,
+            text_length: 0,
+        };
+
+        if self.type_.as_deref() != Some("doc") {
+            validator.error("", "the root node must be a doc");
+        }
+        validator.node(self, "", 1);
+
+        if validator.nodes > MAX_NODES {
+            validator.error("", format!("more than {} nodes", MAX_NODES));
+        }
+        if validator.text_length > MAX_TEXT_LENGTH {
+            validator.error("", format!("more than {} characters", MAX_TEXT_LENGTH));
+        }
+
+        match validator.errors.is_empty() {
+            true => Ok(()),
+            false => {
+                validator.errors.truncate(MAX_ERRORS);
+                Err(validator.errors)
+            }
+        }
+    }
+}
+
+impl Validator {
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.errors.push(SchemaError {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn node(&mut self, jc: &mut TiptapJsonContent, path: &str, depth: usize) {
+        self.nodes += 1;
+        jc.extra.clear();
+
+        let Some(type_) = jc.type_.clone() else {
+            self.error(path, "missing node type");
+            return;
+        };
+        let Some(spec) = node_spec(&type_) else {
+            self.error(path, format!("unknown node type `{}`", type_));
+            return;
+        };
+
+        strip_attrs(&mut jc.attrs, spec.attrs);
+        self.node_attrs(jc, &type_, path);
+
+        if type_ == "text" {
+            match &jc.text {
+                Some(text) if !text.is_empty() => self.text_length += text.chars().count(),
+                _ => self.error(path, "text nodes must have a non-empty text"),
+            }
+        } else if jc.text.is_some() {
+            self.error(path, format!("`{}` nodes can't have a text", type_));
+        }
+
+        match &mut jc.marks {
+            Some(marks) if type_ != "text" && !marks.is_empty() => {
+                self.error(path, format!("`{}` nodes can't have marks", type_));
+            }
+            Some(marks) => {
+                for (i, mark) in marks.iter_mut().enumerate() {
+                    self.mark(mark, &format!("{}/marks/{}", path, i));
+                }
+            }
+            None => {}
+        }
+
+        let Some(content) = &mut jc.content else {
+            return;
+        };
+        if content.is_empty() {
+            return;
+        }
+        if spec.content == ContentKind::Empty {
+            self.error(path, format!("`{}` nodes can't have content", type_));
+            return;
+        }
+        if depth >= MAX_DEPTH {
+            self.error(path, format!("nested deeper than {} nodes", MAX_DEPTH));
+            return;
+        }
+
+        for (i, child) in content.iter_mut().enumerate() {
+            let child_path = format!("{}/content/{}", path, i);
+            if let Some(child_type) = child.type_.as_deref()
+                && node_spec(child_type).is_some()
+                && !allows_child(spec.content, child)
+            {
+                self.error(
+                    &child_path,
+                    format!("`{}` nodes can't contain `{}`", type_, child_type),
+                );
+                continue;
+            }
+            self.node(child, &child_path, depth + 1);
+            // no point in collecting thousands of errors
+            if self.errors.len() >= MAX_ERRORS {
+                return;
+            }
+        }
+    }
+
+    fn node_attrs(&mut self, jc: &TiptapJsonContent, type_: &str, path: &str) {
+        let attr = |name| jc.attrs.as_ref().and_then(|attrs| attrs.get(name));
+        match type_ {
+            "heading"
+                if !attr("level")
+                    .and_then(Value::as_u64)
+                    .is_some_and(|level| (1..=6).contains(&level)) =>
+            {
+                self.error(path, "heading levels must be 1 to 6");
+            }
+            "orderedList" => {
+                if !attr("start").is_none_or(|start| start.is_null() || start.is_u64()) {
+                    self.error(path, "`start` must be a non-negative integer");
+                }
+                if !attr("type").is_none_or(|type_| type_.is_null() || type_.is_string()) {
+                    self.error(path, "`type` must be a string");
+                }
+            }
+            "codeBlock"
+                if !attr("language")
+                    .is_none_or(|language| language.is_null() || language.is_string()) =>
+            {
+                self.error(path, "`language` must be a string");
+            }
+            _ => {}
+        }
+    }
+
+    fn mark(&mut self, mark: &mut TiptapMark, path: &str) {
+        mark.extra.clear();
+        let Some(allowed_attrs) = mark_attrs(&mark.type_) else {
+            self.error(path, format!("unknown mark type `{}`", mark.type_));
+            return;
+        };
+        strip_attrs(&mut mark.attrs, allowed_attrs);
+
+        if mark.type_ != "link" {
+            return;
+        }
+        let attrs = mark.attrs.as_ref();
+        match attrs
+            .and_then(|attrs| attrs.get("href"))
+            .and_then(Value::as_str)
+        {
+            Some(href) if html::is_allowed_href(href) => {}
+            Some(_) => self.error(
+                path,
+                "links must use http, https, mailto, tel, ftp or magnet",
+            ),
+            None => self.error(path, "links must have a `href`"),
+        }
+        for name in ["target", "rel", "class"] {
+            if !attrs
+                .and_then(|attrs| attrs.get(name))
+                .is_none_or(|value| value.is_null() || value.is_string())
+            {
+                self.error(path, format!("`{}` must be a string", name));
+            }
+        }
+    }
+}
+
+fn allows_child(content: ContentKind, child: &TiptapJsonContent) -> bool {
+    let type_ = child.type_.as_deref().unwrap_or_default();
+    match content {
+        ContentKind::Blocks => is_block(type_),
+        ContentKind::ListItems => type_ == "listItem",
+        ContentKind::Inline => matches!(type_, "text" | "hardBreak"),
+        ContentKind::Code => {
+            type_ == "text" && child.marks.as_ref().is_none_or(|marks| marks.is_empty())
+        }
+        ContentKind::Empty => false,
+    }
+}
+
+fn strip_attrs(attrs: &mut Option<HashMap<String, Value>>, allowed: &[&str]) {
+    if let Some(attrs) = attrs {
+        attrs.retain(|name, _| allowed.contains(&name.as_str()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate(doc: Value) -> Result<Value, Vec<SchemaError>> {
+        let mut doc = serde_json::from_value::<TiptapJsonContent>(doc).unwrap();
+        doc.validate()?;
+        Ok(serde_json::to_value(&doc).unwrap())
+    }
+
+    fn paths(doc: Value) -> Vec<String> {
+        validate(doc)
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.path)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_client_documents() {
+        let doc = json!({ "type": "doc", "content": [
+            { "type": "heading", "attrs": { "level": 2 }, "content": [{ "type": "text", "text": "a" }] },
+            { "type": "paragraph" },
+            { "type": "paragraph", "content": [
+                { "type": "text", "text": "b", "marks": [
+                    { "type": "bold" },
+                    { "type": "link", "attrs": {
+                        "href": "https://example.com",
+                        "target": "_blank",
+                        "rel": "noopener noreferrer nofollow",
+                        "class": null,
+                    } },
+                ] },
+                { "type": "hardBreak" },
+            ] },
+            { "type": "orderedList", "attrs": { "start": 1, "type": null }, "content": [
+                { "type": "listItem", "content": [
+                    { "type": "blockquote", "content": [{ "type": "horizontalRule" }] },
+                ] },
+            ] },
+            { "type": "codeBlock", "attrs": { "language": null }, "content": [{ "type": "text", "text": "x" }] },
+        ] });
+        assert!(validate(doc).is_ok());
+    }
+
+    #[test]
+    fn strips_unknown_attrs_and_keys() {
+        let doc = validate(json!({ "type": "doc", "foo": 1, "content": [
+            { "type": "heading", "attrs": { "level": 1, "onclick": "x" }, "content": [
+                { "type": "text", "text": "a", "marks": [{ "type": "italic", "attrs": { "x": 1 }, "y": 2 }] },
+            ] },
+        ] }))
+        .unwrap();
+        assert!(doc.get("foo").is_none());
+        assert_eq!(doc["content"][0]["attrs"], json!({ "level": 1 }));
+        assert_eq!(
+            doc["content"][0]["content"][0]["marks"][0]["attrs"],
+            json!({})
+        );
+        assert!(
+            doc["content"][0]["content"][0]["marks"][0]
+                .get("y")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_types() {
+        assert_eq!(
+            paths(json!({ "type": "doc", "content": [
+                { "type": "image" },
+                { "type": "paragraph", "content": [{ "type": "text", "text": "a", "marks": [{ "type": "highlight" }] }] },
+            ] })),
+            ["/content/0", "/content/1/content/0/marks/0"]
+        );
+        assert_eq!(paths(json!({ "type": "paragraph" })), [""]);
+    }
+
+    #[test]
+    fn rejects_invalid_nesting() {
+        assert_eq!(
+            paths(json!({ "type": "doc", "content": [
+                { "type": "text", "text": "a" },
+                { "type": "bulletList", "content": [{ "type": "paragraph" }] },
+                { "type": "paragraph", "content": [{ "type": "paragraph" }] },
+                { "type": "codeBlock", "content": [{ "type": "text", "text": "a", "marks": [{ "type": "bold" }] }] },
+                { "type": "horizontalRule", "content": [{ "type": "text", "text": "a" }] },
+            ] })),
+            [
+                "/content/0",
+                "/content/1/content/0",
+                "/content/2/content/0",
+                "/content/3/content/0",
+                "/content/4",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_attrs() {
+        let text = json!([{ "type": "text", "text": "a" }]);
+        assert_eq!(
+            paths(json!({ "type": "doc", "content": [
+                { "type": "heading", "attrs": { "level": 7 }, "content": text },
+                { "type": "heading", "content": text },
+                { "type": "orderedList", "attrs": { "start": -1 } },
+                { "type": "codeBlock", "attrs": { "language": 1 } },
+                { "type": "paragraph", "content": [{ "type": "text", "text": "" }] },
+            ] })),
+            [
+                "/content/0",
+                "/content/1",
+                "/content/2",
+                "/content/3",
+                "/content/4/content/0",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_disallowed_links() {
+        let link = |href: &str| {
+            json!({ "type": "doc", "content": [{ "type": "paragraph", "content": [
+                { "type": "text", "text": "a", "marks": [{ "type": "link", "attrs": { "href": href } }] },
+            ] }] })
+        };
+        assert!(validate(link("mailto:jack@example.com")).is_ok());
+        assert_eq!(
+            paths(link("javascript:alert(1)")),
+            ["/content/0/content/0/marks/0"]
+        );
+        assert_eq!(paths(link("data:text/html,x")).len(), 1);
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let mut doc = json!({ "type": "paragraph" });
+        for _ in 0..MAX_DEPTH {
+            doc = json!({ "type": "blockquote", "content": [doc] });
+        }
+        let doc = json!({ "type": "doc", "content": [doc] });
+        let errors = validate(doc).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("nested deeper"));
+    }
+
+    #[test]
+    fn rejects_large_documents() {
+        let paragraph =
+            json!({ "type": "paragraph", "content": [{ "type": "text", "text": "a" }] });
+        let doc = json!({ "type": "doc", "content": vec![paragraph; MAX_NODES / 2] });
+        assert_eq!(paths(doc), [""]);
+
+        let text = "a".repeat(MAX_TEXT_LENGTH + 1);
+        let doc = json!({ "type": "doc", "content": [
+            { "type": "paragraph", "content": [{ "type": "text", "text": text }] },
+        ] });
+        assert_eq!(paths(doc), [""]);
+    }
+}
@@ -0,0 +1,339 @@
+// ref: https://www.sqlite.org/fts5.html
+
+use log::{error, info};
+use std::sync::Arc;
+
+use crate::{db::Repository, tiptap::TiptapJsonContent};
+
+/// private use characters, which can't clash with the text of an entry and
+/// are replaced after html escaping the snippet
+pub const HIGHLIGHT_START: &str = "\u{E000}";
+pub const HIGHLIGHT_END: &str = "\u{E001}";
+pub const SNIPPET_ELLIPSIS: &str = "…";
+/// max number of tokens in a snippet
+pub const SNIPPET_TOKENS: i64 = 24;
+
+const INDEX_BATCH_SIZE: i64 = 100;
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Term { text: String, prefix: bool },
+    Operator(&'static str),
+    Open,
+    Close,
+}
+
+/// user input for searching entries. supports `"phrases"`, `prefix*` terms,
+/// `AND`, `OR`, `NOT` and parentheses, everything else is part of a term, so
+/// that e.g. `-` or `:` in the input don't end up as query syntax.
+#[derive(Debug)]
+pub struct SearchQuery {
+    tokens: Vec<Token>,
+}
+
+impl SearchQuery {
+    /// returns `None` if there is nothing to search for
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut tokens = Vec::new();
+        let mut chars = input.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                c if c.is_whitespace() => {}
+                '(' => tokens.push(Token::Open),
+                ')' => tokens.push(Token::Close),
+                '"' => {
+                    // an unterminated phrase runs until the end of the input
+                    let phrase = chars.by_ref().take_while(|c| *c != '"').collect::<String>();
+                    let prefix = chars.next_if_eq(&'*').is_some();
+                    push_term(&mut tokens, &phrase, prefix);
+                }
+                c => {
+                    let mut word = c.to_string();
+                    while let Some(c) =
+                        chars.next_if(|c| !c.is_whitespace() && !"()\"".contains(*c))
+                    {
+                        word.push(c);
+                    }
+                    match word.as_str() {
+                        "AND" => tokens.push(Token::Operator("AND")),
+                        "OR" => tokens.push(Token::Operator("OR")),
+                        "NOT" => tokens.push(Token::Operator("NOT")),
+                        _ => {
+                            let prefix = word.ends_with('*');
+                            push_term(&mut tokens, word.trim_end_matches('*'), prefix);
+                        }
+                    }
+                }
+            }
+        }
+
+        let tokens = balance(tokens);
+        if !tokens
+            .iter()
+            .any(|token| matches!(token, Token::Term { .. }))
+        {
+            return None;
+        }
+        Some(SearchQuery { tokens })
+    }
+
+    /// ref: https://www.sqlite.org/fts5.html#full_text_query_syntax
+    pub fn to_fts_query(&self) -> String {
+        let mut parts = Vec::new();
+        let mut previous: Option<&Token> = None;
+        for token in &self.tokens {
+            // fts5 only allows the implicit `AND` between phrases
+            if matches!(
+                (previous, token),
+                (Some(Token::Term { .. }), Token::Open)
+                    | (Some(Token::Close), Token::Term { .. } | Token::Open)
+            ) {
+                parts.push("AND".to_string());
+            }
+            parts.push(match token {
+                // ref: https://www.sqlite.org/fts5.html#fts5_strings
+                Token::Term { text, prefix } => {
+                    let mut term = format!("\"{}\"", text.replace('"', "\"\""));
+                    if *prefix {
+                        term.push('*');
+                    }
+                    term
+                }
+                Token::Operator(operator) => operator.to_string(),
+                Token::Open => "(".to_string(),
+                Token::Close => ")".to_string(),
+            });
+            previous = Some(token);
+        }
+        parts.join(" ")
+    }
+
+    /// for postgres `to_tsquery`, with the same meaning as the fts5 query:
+    /// adjacent terms must all match, and `a NOT b` is `a & !b`. a quoted term
+    /// with several words becomes a phrase.
+    /// ref: https://www.postgresql.org/docs/current/textsearch-controls.html#TEXTSEARCH-PARSING-QUERIES
+    pub fn to_tsquery(&self) -> String {
+        let mut parts = Vec::new();
+        let mut operand_before = false;
+        for token in &self.tokens {
+            match token {
+                Token::Term { .. } | Token::Open if operand_before => parts.push("&".to_string()),
+                _ => {}
+            }
+            match token {
+                Token::Term { text, prefix } => {
+                    let mut term = format!("'{}'", text.replace('\\', "\\\\").replace('\'', "''"));
+                    if *prefix {
+                        term.push_str(":*");
+                    }
+                    parts.push(term);
+                }
+                Token::Operator("AND") => parts.push("&".to_string()),
+                Token::Operator("OR") => parts.push("|".to_string()),
+                Token::Operator(_) => parts.push("& !".to_string()),
+                Token::Open => parts.push("(".to_string()),
+                Token::Close => parts.push(")".to_string()),
+            }
+            operand_before = matches!(token, Token::Term { .. } | Token::Close);
+        }
+        parts.join(" ")
+    }
+}
+
+fn push_term(tokens: &mut Vec<Token>, text: &str, prefix: bool) {
+    if text.trim().is_empty() {
+        return;
+    }
+    tokens.push(Token::Term {
+        text: text.to_string(),
+        prefix,
+    });
+}
+
+/// drops operators without an operand on both sides, unmatched parentheses
+/// and empty groups, which would be syntax errors
+fn balance(tokens: Vec<Token>) -> Vec<Token> {
+    let mut result: Vec<Token> = Vec::new();
+    let mut depth = 0;
+
+    for token in tokens {
+        match token {
+            Token::Operator(_) => {
+                if matches!(result.last(), Some(Token::Term { .. } | Token::Close)) {
+                    result.push(token);
+                }
+            }
+            Token::Open => {
+                depth += 1;
+                result.push(token);
+            }
+            Token::Close => {
+                if depth == 0 {
+                    continue;
+                }
+                depth -= 1;
+                while matches!(result.last(), Some(Token::Operator(_))) {
+                    result.pop();
+                }
+                if matches!(result.last(), Some(Token::Open)) {
+                    result.pop();
+                } else {
+                    result.push(token);
+                }
+            }
+            Token::Term { .. } => result.push(token),
+        }
+    }
+
+    while matches!(result.last(), Some(Token::Operator(_) | Token::Open)) {
+        if matches!(result.pop(), Some(Token::Open)) {
+            depth -= 1;
+        }
+        while matches!(result.last(), Some(Token::Operator(_))) {
+            result.pop();
+        }
+    }
+    result.extend((0..depth).map(|_| Token::Close));
+    result
+}
+
+/// escapes the snippet, and wraps the matches in `<mark>`
+pub fn snippet_to_html(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html.replace(HIGHLIGHT_START, "<mark>")
+        .replace(HIGHLIGHT_END, "</mark>")
+}
+
+/// the search text is written together with the entry, this only catches up
+/// on entries written before search existed, or whose search text was cleared
+/// by a migration, e.g. after changing `to_plain_text`. returns the number of
+/// entries.
+pub async fn index_missing_entries(repo: &dyn Repository) -> Result<u64, sqlx::Error> {
+    let mut count = 0;
+    loop {
+        let entries = repo
+            .list_entries_without_search_text(INDEX_BATCH_SIZE)
+            .await?;
+        if entries.is_empty() {
+            return Ok(count);
+        }
+        for entry in entries {
+            // index unparsable entries as empty, so that they are not retried
+            let plain_text = serde_json::from_value::<TiptapJsonContent>(entry.text)
+                .map(|content| content.to_plain_text())
+                .unwrap_or_default();
+            repo.upsert_entry_search_text_by_user_and_date(entry.user_id, entry.date, &plain_text)
+                .await?;
+            count += 1;
+        }
+    }
+}
+
+pub fn spawn_index_task(repo: Arc<dyn Repository>) {
+    tokio::spawn(async move {
+        match index_missing_entries(&*repo).await {
+            Ok(0) => {}
+            Ok(count) => info!("Indexed {} entries for search", count),
+            Err(err) => error!("Failed to index entries for search: {}", err),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_fts_query(input: &str) -> Option<String> {
+        SearchQuery::parse(input).map(|query| query.to_fts_query())
+    }
+
+    fn to_tsquery(input: &str) -> Option<String> {
+        SearchQuery::parse(input).map(|query| query.to_tsquery())
+    }
+
+    #[test]
+    fn quotes_terms() {
+        assert_eq!(to_fts_query("hello world").unwrap(), r#""hello" "world""#);
+        assert_eq!(to_fts_query("e-mail").unwrap(), r#""e-mail""#);
+        assert_eq!(to_fts_query("text:foo").unwrap(), r#""text:foo""#);
+        assert_eq!(to_fts_query("  "), None);
+        assert_eq!(to_fts_query("* \"\""), None);
+    }
+
+    #[test]
+    fn phrases_and_prefixes() {
+        assert_eq!(
+            to_fts_query(r#""hello world" wor*"#).unwrap(),
+            r#""hello world" "wor"*"#
+        );
+        assert_eq!(to_fts_query(r#""hello wo"*"#).unwrap(), r#""hello wo"*"#);
+        // unterminated phrase
+        assert_eq!(to_fts_query(r#"a "b c"#).unwrap(), r#""a" "b c""#);
+    }
+
+    #[test]
+    fn boolean_operators() {
+        assert_eq!(
+            to_fts_query("a OR b NOT c").unwrap(),
+            r#""a" OR "b" NOT "c""#
+        );
+        assert_eq!(
+            to_fts_query("(a OR b) AND c").unwrap(),
+            r#"( "a" OR "b" ) AND "c""#
+        );
+        assert_eq!(
+            to_fts_query("a (b OR c) (d)").unwrap(),
+            r#""a" AND ( "b" OR "c" ) AND ( "d" )"#
+        );
+        // lowercase operators are terms
+        assert_eq!(to_fts_query("a or b").unwrap(), r#""a" "or" "b""#);
+    }
+
+    #[test]
+    fn drops_dangling_syntax() {
+        assert_eq!(to_fts_query("NOT a").unwrap(), r#""a""#);
+        assert_eq!(to_fts_query("a OR").unwrap(), r#""a""#);
+        assert_eq!(to_fts_query("a AND OR b").unwrap(), r#""a" AND "b""#);
+        assert_eq!(to_fts_query("a)").unwrap(), r#""a""#);
+        assert_eq!(to_fts_query("(a OR b").unwrap(), r#"( "a" OR "b" )"#);
+        assert_eq!(to_fts_query("a () b").unwrap(), r#""a" "b""#);
+        assert_eq!(to_fts_query("a (OR) b").unwrap(), r#""a" "b""#);
+        assert_eq!(to_fts_query("a AND (").unwrap(), r#""a""#);
+        assert_eq!(to_fts_query("AND OR NOT"), None);
+    }
+
+    #[test]
+    fn tsquery_syntax() {
+        assert_eq!(to_tsquery("hello world").unwrap(), "'hello' & 'world'");
+        assert_eq!(
+            to_tsquery(r#""hello world" wor*"#).unwrap(),
+            "'hello world' & 'wor':*"
+        );
+        assert_eq!(
+            to_tsquery("(a OR b) c NOT d").unwrap(),
+            "( 'a' | 'b' ) & 'c' & ! 'd'"
+        );
+        assert_eq!(to_tsquery(r"it's a\b").unwrap(), r"'it''s' & 'a\\b'");
+        assert_eq!(to_tsquery("AND OR NOT"), None);
+    }
+
+    #[test]
+    fn escapes_snippet() {
+        let snippet = format!("a <b> & {}match{}…", HIGHLIGHT_START, HIGHLIGHT_END);
+        assert_eq!(
+            snippet_to_html(&snippet),
+            "a &lt;b&gt; &amp; <mark>match</mark>…"
+        );
+    }
+}
@@ -1,7 +0,0 @@
-SELECT
-  `user_id`
-FROM
-  `entry`
-WHERE
-  `user_id` = ?
-  AND `date` = ?;
\ No newline at end of file
@@ -1,4 +1,16 @@
 INSERT INTO
   `entry` (`user_id`, `date`, `text`, `word_count`)
 VALUES
-  (?, ?, ?, ?);
\ No newline at end of file
+  (?, ?, ?, ?)
+ON CONFLICT (`user_id`, `date`) DO UPDATE
+SET
+  `text` = `excluded`.`text`,
+  `word_count` = `excluded`.`word_count`,
+  `revision` = `entry`.`revision` + 1,
+  `created_at` = CURRENT_TIMESTAMP,
+  `updated_at` = CURRENT_TIMESTAMP,
+  `deleted_at` = NULL
+WHERE
+  `entry`.`deleted_at` IS NOT NULL
+RETURNING
+  `revision`;
\ No newline at end of file
@@ -0,0 +1,31 @@
+INSERT INTO
+  `entry_revision` (
+    `user_id`,
+    `date`,
+    `text`,
+    `word_count`,
+    `revision`,
+    `saved_at`
+  )
+SELECT
+  `user_id`,
+  `date`,
+  `text`,
+  `word_count`,
+  `revision`,
+  `updated_at`
+FROM
+  `entry`
+WHERE
+  `user_id` = ?
+  AND `date` = ?
+  AND NOT EXISTS (
+    SELECT
+      1
+    FROM
+      `entry_revision`
+    WHERE
+      `entry_revision`.`user_id` = `entry`.`user_id`
+      AND `entry_revision`.`date` = `entry`.`date`
+      AND `entry_revision`.`created_at` > DATETIME('now', ?)
+  );
\ No newline at end of file
@@ -0,0 +1,4 @@
+INSERT INTO
+  `recovery_code` (`user_id`, `code_hash`)
+VALUES
+  (?, ?);
\ No newline at end of file
@@ -0,0 +1,6 @@
+INSERT INTO
+  `session` (`user_id`, `refresh_token_hash`, `expires_at`)
+VALUES
+  (?, ?, ?)
+RETURNING
+  `id`;
\ No newline at end of file
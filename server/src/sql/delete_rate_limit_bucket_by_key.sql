@@ -0,0 +1,3 @@
+DELETE FROM `rate_limit_bucket`
+WHERE
+  `key` = ?;
\ No newline at end of file
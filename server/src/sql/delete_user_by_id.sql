@@ -0,0 +1,3 @@
+DELETE FROM `user`
+WHERE
+  `id` = ?;
\ No newline at end of file
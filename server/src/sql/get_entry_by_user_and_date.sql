@@ -1,11 +1,14 @@
 SELECT
   `user_id`,
-  `date`,
+  `date` AS "date: time::Date",
   `text` AS "text: serde_json::Value",
   `word_count`,
-  `created_at`
+  `revision`,
+  `created_at`,
+  `updated_at`
 FROM
   `entry`
 WHERE
   `user_id` = ?
-  AND `date` = ?;
\ No newline at end of file
+  AND `date` = ?
+  AND `deleted_at` IS NULL;
\ No newline at end of file
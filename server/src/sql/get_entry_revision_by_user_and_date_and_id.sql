@@ -0,0 +1,13 @@
+SELECT
+  `id`,
+  `revision`,
+  `text` AS "text: serde_json::Value",
+  `word_count`,
+  `saved_at`,
+  `created_at`
+FROM
+  `entry_revision`
+WHERE
+  `user_id` = ?
+  AND `date` = ?
+  AND `id` = ?;
\ No newline at end of file
@@ -0,0 +1,9 @@
+SELECT
+  COUNT(*) AS "entries!: i64",
+  IFNULL(SUM(`word_count`), 0) AS "words!: i64",
+  IFNULL(AVG(`word_count`), 0.0) AS "average_words!: f64"
+FROM
+  `entry`
+WHERE
+  `user_id` = ?
+  AND `deleted_at` IS NULL;
\ No newline at end of file
@@ -0,0 +1,13 @@
+SELECT
+  `date` AS "date: time::Date",
+  `word_count`
+FROM
+  `entry`
+WHERE
+  `user_id` = ?
+  AND `deleted_at` IS NULL
+ORDER BY
+  `word_count` DESC,
+  `date`
+LIMIT
+  1;
\ No newline at end of file
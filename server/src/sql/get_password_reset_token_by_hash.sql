@@ -0,0 +1,9 @@
+SELECT
+  `id`,
+  `user_id`,
+  `expires_at`,
+  `used_at`
+FROM
+  `password_reset_token`
+WHERE
+  `token_hash` = ?;
\ No newline at end of file
@@ -0,0 +1,7 @@
+SELECT
+  `tokens`,
+  `updated_at`
+FROM
+  `rate_limit_bucket`
+WHERE
+  `key` = ?;
\ No newline at end of file
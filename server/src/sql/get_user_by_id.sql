@@ -3,7 +3,9 @@ SELECT
   `email`,
   `name`,
   `password`,
-  `created_at`
+  `created_at`,
+  `daily_word_goal`,
+  `timezone`
 FROM
   `user`
 WHERE
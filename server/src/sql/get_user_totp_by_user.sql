@@ -0,0 +1,8 @@
+SELECT
+  `secret`,
+  `enabled_at`,
+  `last_used_step`
+FROM
+  `user_totp`
+WHERE
+  `user_id` = ?;
\ No newline at end of file
@@ -0,0 +1,14 @@
+SELECT
+  `user_id`,
+  `date` AS "date: time::Date",
+  `text` AS "text: serde_json::Value",
+  `word_count`
+FROM
+  `entry`
+WHERE
+  (`user_id`, `date`) > (?, ?)
+ORDER BY
+  `user_id`,
+  `date`
+LIMIT
+  ?;
\ No newline at end of file
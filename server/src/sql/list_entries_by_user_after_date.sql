@@ -0,0 +1,18 @@
+SELECT
+  `user_id`,
+  `date` AS "date: time::Date",
+  `text` AS "text: serde_json::Value",
+  `word_count`,
+  `revision`,
+  `created_at`,
+  `updated_at`
+FROM
+  `entry`
+WHERE
+  `user_id` = ?
+  AND `date` > IFNULL(?, '')
+  AND `deleted_at` IS NULL
+ORDER BY
+  `date`
+LIMIT
+  ?;
\ No newline at end of file
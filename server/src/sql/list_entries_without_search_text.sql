@@ -0,0 +1,12 @@
+SELECT
+  `entry`.`user_id`,
+  `entry`.`date` AS "date: time::Date",
+  `entry`.`text` AS "text: serde_json::Value"
+FROM
+  `entry`
+  LEFT JOIN `entry_search` ON `entry_search`.`user_id` = `entry`.`user_id`
+  AND `entry_search`.`date` = `entry`.`date`
+WHERE
+  `entry_search`.`id` IS NULL
+LIMIT
+  ?;
\ No newline at end of file
@@ -1,6 +1,7 @@
 SELECT
-  `date`
+  `date` AS "date: time::Date"
 FROM
   `entry`
 WHERE
-  `user_id` = ?;
\ No newline at end of file
+  `user_id` = ?
+  AND `deleted_at` IS NULL;
\ No newline at end of file
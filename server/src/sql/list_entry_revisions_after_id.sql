@@ -0,0 +1,12 @@
+SELECT
+  `id`,
+  `text` AS "text: serde_json::Value",
+  `word_count`
+FROM
+  `entry_revision`
+WHERE
+  `id` > ?
+ORDER BY
+  `id`
+LIMIT
+  ?;
\ No newline at end of file
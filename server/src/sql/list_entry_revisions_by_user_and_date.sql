@@ -0,0 +1,13 @@
+SELECT
+  `id`,
+  `revision`,
+  `word_count`,
+  `saved_at`,
+  `created_at`
+FROM
+  `entry_revision`
+WHERE
+  `user_id` = ?
+  AND `date` = ?
+ORDER BY
+  `id` DESC;
\ No newline at end of file
@@ -0,0 +1,20 @@
+SELECT
+  `entry`.`date` AS "date: time::Date",
+  `entry`.`word_count`,
+  `entry`.`created_at`,
+  `entry`.`updated_at`,
+  SUBSTR(IFNULL(`entry_search`.`text`, ''), 1, ?) AS "preview!: String"
+FROM
+  `entry`
+  LEFT JOIN `entry_search` ON `entry_search`.`user_id` = `entry`.`user_id`
+  AND `entry_search`.`date` = `entry`.`date`
+WHERE
+  `entry`.`user_id` = ?
+  AND `entry`.`deleted_at` IS NULL
+  AND `entry`.`date` >= IFNULL(?, '')
+  AND `entry`.`date` <= IFNULL(?, '9999-12-31')
+  AND `entry`.`date` > IFNULL(?, '')
+ORDER BY
+  `entry`.`date`
+LIMIT
+  ?;
\ No newline at end of file
@@ -0,0 +1,11 @@
+SELECT
+  `date` AS "date: time::Date",
+  `word_count`
+FROM
+  `entry`
+WHERE
+  `user_id` = ?
+  AND `deleted_at` IS NULL
+  AND `date` BETWEEN ? AND ?
+ORDER BY
+  `date`;
\ No newline at end of file
@@ -0,0 +1,10 @@
+SELECT
+  `date` AS "date: time::Date"
+FROM
+  `entry`
+WHERE
+  `user_id` = ?
+  AND `deleted_at` IS NULL
+  AND `word_count` >= ?
+ORDER BY
+  `date`;
\ No newline at end of file
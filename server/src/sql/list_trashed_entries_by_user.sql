@@ -0,0 +1,11 @@
+SELECT
+  `date` AS "date: time::Date",
+  `word_count`,
+  `deleted_at` AS "deleted_at!"
+FROM
+  `entry`
+WHERE
+  `user_id` = ?
+  AND `deleted_at` IS NOT NULL
+ORDER BY
+  `deleted_at` DESC;
\ No newline at end of file
@@ -0,0 +1,11 @@
+SELECT
+  CAST(STRFTIME('%w', `date`) AS INTEGER) AS "key!: i64",
+  COUNT(*) AS "entries!: i64",
+  SUM(`word_count`) AS "words!: i64"
+FROM
+  `entry`
+WHERE
+  `user_id` = ?
+  AND `deleted_at` IS NULL
+GROUP BY
+  1;
\ No newline at end of file
@@ -0,0 +1,16 @@
+INSERT INTO
+  "Entry" ("user_id", "date", "text", "word_count")
+VALUES
+  ($1, $2, $3, $4)
+ON CONFLICT ("user_id", "date") DO UPDATE
+SET
+  "text" = "excluded"."text",
+  "word_count" = "excluded"."word_count",
+  "revision" = "Entry"."revision" + 1,
+  "created_at" = CURRENT_TIMESTAMP,
+  "updated_at" = CURRENT_TIMESTAMP,
+  "deleted_at" = NULL
+WHERE
+  "Entry"."deleted_at" IS NOT NULL
+RETURNING
+  "revision";
\ No newline at end of file
@@ -0,0 +1,31 @@
+INSERT INTO
+  "entry_revision" (
+    "user_id",
+    "date",
+    "text",
+    "word_count",
+    "revision",
+    "saved_at"
+  )
+SELECT
+  "user_id",
+  "date",
+  "text",
+  "word_count",
+  "revision",
+  "updated_at"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "date" = $2
+  AND NOT EXISTS (
+    SELECT
+      1
+    FROM
+      "entry_revision"
+    WHERE
+      "entry_revision"."user_id" = "Entry"."user_id"
+      AND "entry_revision"."date" = "Entry"."date"
+      AND "entry_revision"."created_at" > CURRENT_TIMESTAMP - MAKE_INTERVAL(secs => $3)
+  );
\ No newline at end of file
@@ -0,0 +1,4 @@
+INSERT INTO
+  "password_reset_token" ("user_id", "token_hash", "expires_at")
+VALUES
+  ($1, $2, $3);
\ No newline at end of file
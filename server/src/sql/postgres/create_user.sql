@@ -0,0 +1,4 @@
+INSERT INTO
+  "User" ("email", "name", "password")
+VALUES
+  ($1, $2, $3);
\ No newline at end of file
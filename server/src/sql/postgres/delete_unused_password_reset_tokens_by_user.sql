@@ -0,0 +1,4 @@
+DELETE FROM "password_reset_token"
+WHERE
+  "user_id" = $1
+  AND "used_at" IS NULL;
\ No newline at end of file
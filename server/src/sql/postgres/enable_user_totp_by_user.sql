@@ -0,0 +1,7 @@
+UPDATE "user_totp"
+SET
+  "enabled_at" = CURRENT_TIMESTAMP,
+  "last_used_step" = $1
+WHERE
+  "user_id" = $2
+  AND "enabled_at" IS NULL;
\ No newline at end of file
@@ -0,0 +1,14 @@
+SELECT
+  "user_id",
+  "date",
+  "text",
+  "word_count",
+  "revision",
+  "created_at",
+  "updated_at"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "date" = $2
+  AND "deleted_at" IS NULL;
\ No newline at end of file
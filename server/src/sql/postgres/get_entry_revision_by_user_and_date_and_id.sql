@@ -0,0 +1,13 @@
+SELECT
+  "id",
+  "revision",
+  "text",
+  "word_count",
+  "saved_at",
+  "created_at"
+FROM
+  "entry_revision"
+WHERE
+  "user_id" = $1
+  AND "date" = $2
+  AND "id" = $3;
\ No newline at end of file
@@ -0,0 +1,9 @@
+SELECT
+  COUNT(*) AS "entries",
+  COALESCE(SUM("word_count"), 0)::BIGINT AS "words",
+  COALESCE(AVG("word_count"), 0)::DOUBLE PRECISION AS "average_words"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "deleted_at" IS NULL;
\ No newline at end of file
@@ -0,0 +1,13 @@
+SELECT
+  "date",
+  "word_count"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "deleted_at" IS NULL
+ORDER BY
+  "word_count" DESC,
+  "date"
+LIMIT
+  1;
\ No newline at end of file
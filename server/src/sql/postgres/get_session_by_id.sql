@@ -0,0 +1,10 @@
+SELECT
+  "id",
+  "user_id",
+  "refresh_token_hash",
+  "expires_at",
+  "revoked_at"
+FROM
+  "session"
+WHERE
+  "id" = $1;
\ No newline at end of file
@@ -0,0 +1,12 @@
+SELECT
+  "id",
+  "email",
+  "name",
+  "password",
+  "created_at",
+  "daily_word_goal",
+  "timezone"
+FROM
+  "User"
+WHERE
+  "email" = $1;
\ No newline at end of file
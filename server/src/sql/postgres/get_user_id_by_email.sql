@@ -0,0 +1,6 @@
+SELECT
+  "id"
+FROM
+  "User"
+WHERE
+  "email" = $1;
\ No newline at end of file
@@ -0,0 +1,14 @@
+SELECT
+  "user_id",
+  "date",
+  "text",
+  "word_count"
+FROM
+  "Entry"
+WHERE
+  ("user_id", "date") > ($1, $2)
+ORDER BY
+  "user_id",
+  "date"
+LIMIT
+  $3;
\ No newline at end of file
@@ -0,0 +1,18 @@
+SELECT
+  "user_id",
+  "date",
+  "text",
+  "word_count",
+  "revision",
+  "created_at",
+  "updated_at"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "date" > COALESCE($2, '-infinity')
+  AND "deleted_at" IS NULL
+ORDER BY
+  "date"
+LIMIT
+  $3;
\ No newline at end of file
@@ -0,0 +1,12 @@
+SELECT
+  "Entry"."user_id",
+  "Entry"."date",
+  "Entry"."text"
+FROM
+  "Entry"
+  LEFT JOIN "entry_search" ON "entry_search"."user_id" = "Entry"."user_id"
+  AND "entry_search"."date" = "Entry"."date"
+WHERE
+  "entry_search"."id" IS NULL
+LIMIT
+  $1;
\ No newline at end of file
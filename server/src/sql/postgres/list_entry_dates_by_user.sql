@@ -0,0 +1,7 @@
+SELECT
+  "date"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "deleted_at" IS NULL;
\ No newline at end of file
@@ -0,0 +1,12 @@
+SELECT
+  "id",
+  "text",
+  "word_count"
+FROM
+  "entry_revision"
+WHERE
+  "id" > $1
+ORDER BY
+  "id"
+LIMIT
+  $2;
\ No newline at end of file
@@ -0,0 +1,20 @@
+SELECT
+  "Entry"."date",
+  "Entry"."word_count",
+  "Entry"."created_at",
+  "Entry"."updated_at",
+  SUBSTR(COALESCE("entry_search"."text", ''), 1, $1::INTEGER) AS "preview"
+FROM
+  "Entry"
+  LEFT JOIN "entry_search" ON "entry_search"."user_id" = "Entry"."user_id"
+  AND "entry_search"."date" = "Entry"."date"
+WHERE
+  "Entry"."user_id" = $2
+  AND "Entry"."deleted_at" IS NULL
+  AND "Entry"."date" >= COALESCE($3, '-infinity')
+  AND "Entry"."date" <= COALESCE($4, 'infinity')
+  AND "Entry"."date" > COALESCE($5, '-infinity')
+ORDER BY
+  "Entry"."date"
+LIMIT
+  $6;
\ No newline at end of file
@@ -0,0 +1,11 @@
+SELECT
+  "date",
+  "word_count"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "deleted_at" IS NULL
+  AND "date" BETWEEN $2 AND $3
+ORDER BY
+  "date";
\ No newline at end of file
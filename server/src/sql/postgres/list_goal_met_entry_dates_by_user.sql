@@ -0,0 +1,10 @@
+SELECT
+  "date"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "deleted_at" IS NULL
+  AND "word_count" >= $2
+ORDER BY
+  "date";
\ No newline at end of file
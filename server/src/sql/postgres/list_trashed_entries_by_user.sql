@@ -0,0 +1,11 @@
+SELECT
+  "date",
+  "word_count",
+  "deleted_at"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "deleted_at" IS NOT NULL
+ORDER BY
+  "deleted_at" DESC;
\ No newline at end of file
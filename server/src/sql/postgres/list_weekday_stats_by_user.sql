@@ -0,0 +1,11 @@
+SELECT
+  EXTRACT(DOW FROM "date")::BIGINT AS "key",
+  COUNT(*) AS "entries",
+  SUM("word_count")::BIGINT AS "words"
+FROM
+  "Entry"
+WHERE
+  "user_id" = $1
+  AND "deleted_at" IS NULL
+GROUP BY
+  1;
\ No newline at end of file
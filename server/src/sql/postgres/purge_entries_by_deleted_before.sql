@@ -0,0 +1,3 @@
+DELETE FROM "Entry"
+WHERE
+  "deleted_at" < CURRENT_TIMESTAMP - MAKE_INTERVAL(secs => $1);
\ No newline at end of file
@@ -0,0 +1,4 @@
+DELETE FROM "Entry"
+WHERE
+  "user_id" = $1
+  AND "deleted_at" IS NOT NULL;
\ No newline at end of file
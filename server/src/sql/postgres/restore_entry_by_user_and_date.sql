@@ -0,0 +1,7 @@
+UPDATE "Entry"
+SET
+  "deleted_at" = NULL
+WHERE
+  "user_id" = $1
+  AND "date" = $2
+  AND "deleted_at" IS NOT NULL;
\ No newline at end of file
@@ -0,0 +1,8 @@
+UPDATE "session"
+SET
+  "refresh_token_hash" = $1,
+  "expires_at" = $2
+WHERE
+  "id" = $3
+  AND "refresh_token_hash" = $4
+  AND "revoked_at" IS NULL;
\ No newline at end of file
@@ -0,0 +1,19 @@
+SELECT
+  "entry_search"."date",
+  TS_HEADLINE('simple', "entry_search"."text", "query", $1) AS "snippet",
+  -TS_RANK("entry_search"."search", "query")::DOUBLE PRECISION AS "rank"
+FROM
+  "entry_search"
+  JOIN "Entry" ON "Entry"."user_id" = "entry_search"."user_id"
+  AND "Entry"."date" = "entry_search"."date"
+  CROSS JOIN TO_TSQUERY('simple', $2) AS "query"
+WHERE
+  "entry_search"."search" @@ "query"
+  AND "entry_search"."user_id" = $3
+  AND "Entry"."deleted_at" IS NULL
+ORDER BY
+  "rank"
+LIMIT
+  $4
+OFFSET
+  $5;
\ No newline at end of file
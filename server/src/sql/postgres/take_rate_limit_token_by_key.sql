@@ -0,0 +1,7 @@
+UPDATE "rate_limit_bucket"
+SET
+  "tokens" = LEAST($1, "tokens" + GREATEST($2 - "updated_at", 0) * $3) - 1,
+  "updated_at" = $2
+WHERE
+  "key" = $4
+  AND LEAST($1, "tokens" + GREATEST($2 - "updated_at", 0) * $3) >= 1;
\ No newline at end of file
@@ -0,0 +1,7 @@
+UPDATE "Entry"
+SET
+  "deleted_at" = CURRENT_TIMESTAMP
+WHERE
+  "user_id" = $1
+  AND "date" = $2
+  AND "deleted_at" IS NULL;
\ No newline at end of file
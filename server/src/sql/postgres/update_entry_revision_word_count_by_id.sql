@@ -0,0 +1,5 @@
+UPDATE "entry_revision"
+SET
+  "word_count" = $1
+WHERE
+  "id" = $2;
\ No newline at end of file
@@ -0,0 +1,13 @@
+UPDATE "Entry"
+SET
+  "text" = $1,
+  "word_count" = $2,
+  "revision" = "revision" + 1,
+  "updated_at" = CURRENT_TIMESTAMP
+WHERE
+  "user_id" = $3
+  AND "date" = $4
+  AND "deleted_at" IS NULL
+  AND "revision" = COALESCE($5, "revision")
+RETURNING
+  "revision";
\ No newline at end of file
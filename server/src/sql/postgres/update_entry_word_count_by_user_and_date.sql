@@ -0,0 +1,6 @@
+UPDATE "Entry"
+SET
+  "word_count" = $1
+WHERE
+  "user_id" = $2
+  AND "date" = $3;
\ No newline at end of file
@@ -0,0 +1,9 @@
+UPDATE "User"
+SET
+  "name" = COALESCE($1, "name"),
+  "email" = COALESCE($2, "email"),
+  "password" = COALESCE($3, "password"),
+  "daily_word_goal" = COALESCE($4, "daily_word_goal"),
+  "timezone" = COALESCE($5, "timezone")
+WHERE
+  "id" = $6;
\ No newline at end of file
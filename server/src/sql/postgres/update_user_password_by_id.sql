@@ -0,0 +1,5 @@
+UPDATE "User"
+SET
+  "password" = $1
+WHERE
+  "id" = $2;
\ No newline at end of file
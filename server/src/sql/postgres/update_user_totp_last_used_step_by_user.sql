@@ -0,0 +1,9 @@
+UPDATE "user_totp"
+SET
+  "last_used_step" = $1
+WHERE
+  "user_id" = $2
+  AND (
+    "last_used_step" IS NULL
+    OR "last_used_step" < $3
+  );
\ No newline at end of file
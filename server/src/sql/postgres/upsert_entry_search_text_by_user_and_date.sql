@@ -0,0 +1,7 @@
+INSERT INTO
+  "entry_search" ("user_id", "date", "text")
+VALUES
+  ($1, $2, $3)
+ON CONFLICT ("user_id", "date") DO UPDATE
+SET
+  "text" = "excluded"."text";
\ No newline at end of file
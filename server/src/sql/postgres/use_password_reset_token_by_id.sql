@@ -0,0 +1,6 @@
+UPDATE "password_reset_token"
+SET
+  "used_at" = CURRENT_TIMESTAMP
+WHERE
+  "id" = $1
+  AND "used_at" IS NULL;
\ No newline at end of file
@@ -0,0 +1,3 @@
+DELETE FROM `entry`
+WHERE
+  `deleted_at` < DATETIME('now', ?);
\ No newline at end of file
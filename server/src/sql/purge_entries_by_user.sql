@@ -1,4 +1,4 @@
 DELETE FROM `entry`
 WHERE
   `user_id` = ?
-  AND `date` = ?;
\ No newline at end of file
+  AND `deleted_at` IS NOT NULL;
\ No newline at end of file
@@ -0,0 +1,5 @@
+DELETE FROM `entry`
+WHERE
+  `user_id` = ?
+  AND `date` = ?
+  AND `deleted_at` IS NOT NULL;
\ No newline at end of file
@@ -0,0 +1,7 @@
+UPDATE `entry`
+SET
+  `deleted_at` = NULL
+WHERE
+  `user_id` = ?
+  AND `date` = ?
+  AND `deleted_at` IS NOT NULL;
\ No newline at end of file
@@ -0,0 +1,6 @@
+UPDATE `rate_limit_bucket`
+SET
+  `tokens` = MIN(?, `tokens` + MAX(? - `updated_at`, 0) * ? + 1),
+  `updated_at` = ?
+WHERE
+  `key` = ?;
\ No newline at end of file
@@ -0,0 +1,6 @@
+UPDATE `session`
+SET
+  `revoked_at` = CURRENT_TIMESTAMP
+WHERE
+  `user_id` = ?
+  AND `revoked_at` IS NULL;
\ No newline at end of file
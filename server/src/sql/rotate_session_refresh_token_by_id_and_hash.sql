@@ -0,0 +1,8 @@
+UPDATE `session`
+SET
+  `refresh_token_hash` = ?,
+  `expires_at` = ?
+WHERE
+  `id` = ?
+  AND `refresh_token_hash` = ?
+  AND `revoked_at` IS NULL;
\ No newline at end of file
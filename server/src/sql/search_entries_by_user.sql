@@ -0,0 +1,19 @@
+SELECT
+  `entry_search`.`date` AS "date: time::Date",
+  SNIPPET(`entry_fts`, 0, ?, ?, ?, ?) AS "snippet!: String",
+  BM25(`entry_fts`) AS "rank!: f64"
+FROM
+  `entry_fts`
+  JOIN `entry_search` ON `entry_search`.`id` = `entry_fts`.`rowid`
+  JOIN `entry` ON `entry`.`user_id` = `entry_search`.`user_id`
+  AND `entry`.`date` = `entry_search`.`date`
+WHERE
+  `entry_fts` MATCH ?
+  AND `entry_search`.`user_id` = ?
+  AND `entry`.`deleted_at` IS NULL
+ORDER BY
+  `rank`
+LIMIT
+  ?
+OFFSET
+  ?;
\ No newline at end of file
@@ -0,0 +1,7 @@
+UPDATE `rate_limit_bucket`
+SET
+  `tokens` = MIN(?, `tokens` + MAX(? - `updated_at`, 0) * ?) - 1,
+  `updated_at` = ?
+WHERE
+  `key` = ?
+  AND MIN(?, `tokens` + MAX(? - `updated_at`, 0) * ?) >= 1;
\ No newline at end of file
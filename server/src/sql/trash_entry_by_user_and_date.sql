@@ -0,0 +1,7 @@
+UPDATE `entry`
+SET
+  `deleted_at` = CURRENT_TIMESTAMP
+WHERE
+  `user_id` = ?
+  AND `date` = ?
+  AND `deleted_at` IS NULL;
\ No newline at end of file
@@ -0,0 +1,5 @@
+UPDATE `entry_revision`
+SET
+  `word_count` = ?
+WHERE
+  `id` = ?;
\ No newline at end of file
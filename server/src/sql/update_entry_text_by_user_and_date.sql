@@ -1,7 +1,13 @@
 UPDATE `entry`
 SET
   `text` = ?,
-  `word_count` = ?
+  `word_count` = ?,
+  `revision` = `revision` + 1,
+  `updated_at` = CURRENT_TIMESTAMP
 WHERE
   `user_id` = ?
-  AND `date` = ?;
\ No newline at end of file
+  AND `date` = ?
+  AND `deleted_at` IS NULL
+  AND `revision` = IFNULL(?, `revision`)
+RETURNING
+  `revision`;
\ No newline at end of file
@@ -0,0 +1,6 @@
+UPDATE `entry`
+SET
+  `word_count` = ?
+WHERE
+  `user_id` = ?
+  AND `date` = ?;
\ No newline at end of file
@@ -0,0 +1,9 @@
+UPDATE `user`
+SET
+  `name` = COALESCE(?, `name`),
+  `email` = COALESCE(?, `email`),
+  `password` = COALESCE(?, `password`),
+  `daily_word_goal` = COALESCE(?, `daily_word_goal`),
+  `timezone` = COALESCE(?, `timezone`)
+WHERE
+  `id` = ?;
\ No newline at end of file
@@ -0,0 +1,5 @@
+UPDATE `user`
+SET
+  `password` = ?
+WHERE
+  `id` = ?;
\ No newline at end of file
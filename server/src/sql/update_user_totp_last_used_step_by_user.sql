@@ -0,0 +1,9 @@
+UPDATE `user_totp`
+SET
+  `last_used_step` = ?
+WHERE
+  `user_id` = ?
+  AND (
+    `last_used_step` IS NULL
+    OR `last_used_step` < ?
+  );
\ No newline at end of file
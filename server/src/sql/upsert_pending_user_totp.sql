@@ -0,0 +1,10 @@
+INSERT INTO
+  `user_totp` (`user_id`, `secret`)
+VALUES
+  (?, ?)
+ON CONFLICT (`user_id`) DO UPDATE
+SET
+  `secret` = `excluded`.`secret`,
+  `last_used_step` = NULL
+WHERE
+  `enabled_at` IS NULL;
\ No newline at end of file
@@ -0,0 +1,7 @@
+UPDATE `recovery_code`
+SET
+  `used_at` = CURRENT_TIMESTAMP
+WHERE
+  `user_id` = ?
+  AND `code_hash` = ?
+  AND `used_at` IS NULL;
\ No newline at end of file
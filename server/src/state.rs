@@ -0,0 +1,15 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+
+use crate::{db::Repository, hasher::Hasher, mailer::Mailer, rate_limit::RateLimiter};
+
+// handlers extract only the parts they need, e.g. `State<Arc<dyn Repository>>`
+// ref: https://docs.rs/axum/0.8.4/axum/extract/struct.State.html#substates
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub repo: Arc<dyn Repository>,
+    pub mailer: Arc<dyn Mailer>,
+    pub hasher: Hasher,
+    pub rate_limiter: RateLimiter,
+}
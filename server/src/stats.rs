@@ -0,0 +1,202 @@
+// aggregates are computed by the database, this only fills in the buckets and days
+// without entries
+
+use serde::Serialize;
+use time::{Date, Duration};
+
+use crate::{
+    datetime::AppDate,
+    db::{DbEntryDateWordCount, DbEntryHistogramBucket, Repository},
+};
+
+/// length of the daily series if the request doesn't specify `from`
+pub const DEFAULT_RANGE_DAYS: i64 = 30;
+pub const MAX_RANGE_DAYS: i64 = 731;
+
+#[derive(Serialize)]
+pub struct HistogramBucket {
+    entries: i64,
+    words: i64,
+}
+
+#[derive(Serialize)]
+pub struct Stats {
+    total_words: i64,
+    total_entries: i64,
+    average_words: f64,
+    longest_entry: Option<DbEntryDateWordCount>,
+    /// index 0 is sunday
+    weekdays: Vec<HistogramBucket>,
+    /// index 0 is january
+    months: Vec<HistogramBucket>,
+    /// every day from `from` to `to`, including those without an entry
+    daily: Vec<DbEntryDateWordCount>,
+}
+
+/// `to` defaults to `today` and `from` to `DEFAULT_RANGE_DAYS` before `to`.
+/// returns `None` if the range is reversed or longer than `MAX_RANGE_DAYS`.
+pub fn date_range(from: Option<Date>, to: Option<Date>, today: Date) -> Option<(Date, Date)> {
+    let to = to.unwrap_or(today);
+    let from = from.unwrap_or(to - Duration::days(DEFAULT_RANGE_DAYS - 1));
+    let days = (to - from).whole_days() + 1;
+    (1..=MAX_RANGE_DAYS).contains(&days).then_some((from, to))
+}
+
+pub async fn get_stats(
+    repo: &dyn Repository,
+    user_id: i64,
+    from: Date,
+    to: Date,
+) -> Result<Stats, sqlx::Error> {
+    let totals = repo.get_entry_stats_by_user(user_id).await?;
+    let longest_entry = repo.get_longest_entry_by_user(user_id).await?;
+    let weekdays = repo.list_weekday_stats_by_user(user_id).await?;
+    let months = repo.list_month_stats_by_user(user_id).await?;
+    let entries = repo
+        .list_entry_word_counts_by_user_and_date_range(user_id, from, to)
+        .await?;
+
+    Ok(Stats {
+        total_words: totals.words,
+        total_entries: totals.entries,
+        average_words: totals.average_words,
+        longest_entry,
+        weekdays: histogram(weekdays, 0, 7),
+        months: histogram(months, 1, 12),
+        daily: daily_series(from, to, entries),
+    })
+}
+
+/// `len` buckets for the keys starting at `first_key`
+fn histogram(
+    rows: Vec<DbEntryHistogramBucket>,
+    first_key: i64,
+    len: usize,
+) -> Vec<HistogramBucket> {
+    let mut buckets: Vec<_> = (0..len)
+        .map(|_| HistogramBucket {
+            entries: 0,
+            words: 0,
+        })
+        .collect();
+    for row in rows {
+        let index = usize::try_from(row.key - first_key).ok();
+        if let Some(bucket) = index.and_then(|index| buckets.get_mut(index)) {
+            bucket.entries = row.entries;
+            bucket.words = row.words;
+        }
+    }
+    buckets
+}
+
+/// `entries` must be in date order
+fn daily_series(
+    from: Date,
+    to: Date,
+    entries: Vec<DbEntryDateWordCount>,
+) -> Vec<DbEntryDateWordCount> {
+    let mut entries = entries.into_iter().peekable();
+    let mut series = Vec::new();
+    let mut date = Some(from);
+    while let Some(day) = date.filter(|day| *day <= to) {
+        let word_count = entries
+            .next_if(|entry| *entry.date == day)
+            .map_or(0, |entry| entry.word_count);
+        series.push(DbEntryDateWordCount {
+            date: AppDate(day),
+            word_count,
+        });
+        date = day.next_day();
+    }
+    series
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn entry(date: Date, word_count: i64) -> DbEntryDateWordCount {
+        DbEntryDateWordCount {
+            date: AppDate(date),
+            word_count,
+        }
+    }
+
+    #[test]
+    fn date_range_defaults() {
+        let today = date!(2026 - 10 - 18);
+        assert_eq!(
+            date_range(None, None, today),
+            Some((date!(2026 - 09 - 19), today))
+        );
+        assert_eq!(
+            date_range(None, Some(date!(2026 - 01 - 30)), today),
+            Some((date!(2026 - 01 - 01), date!(2026 - 01 - 30)))
+        );
+        assert_eq!(
+            date_range(Some(date!(2026 - 10 - 01)), None, today),
+            Some((date!(2026 - 10 - 01), today))
+        );
+    }
+
+    #[test]
+    fn date_range_rejects_invalid_ranges() {
+        let today = date!(2026 - 10 - 18);
+        assert_eq!(
+            date_range(Some(today), Some(today), today),
+            Some((today, today))
+        );
+        assert_eq!(
+            date_range(Some(today.next_day().unwrap()), Some(today), today),
+            None
+        );
+        assert_eq!(date_range(Some(date!(2020 - 01 - 01)), None, today), None);
+    }
+
+    #[test]
+    fn daily_series_fills_missing_days() {
+        let series = daily_series(
+            date!(2026 - 02 - 27),
+            date!(2026 - 03 - 02),
+            vec![
+                entry(date!(2026 - 02 - 28), 10),
+                entry(date!(2026 - 03 - 02), 3),
+            ],
+        );
+        let series: Vec<_> = series
+            .iter()
+            .map(|day| (day.date.to_string(), day.word_count))
+            .collect();
+        assert_eq!(
+            series,
+            vec![
+                ("2026-02-27".to_string(), 0),
+                ("2026-02-28".to_string(), 10),
+                ("2026-03-01".to_string(), 0),
+                ("2026-03-02".to_string(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn histogram_places_buckets_by_key() {
+        let rows = vec![
+            DbEntryHistogramBucket {
+                key: 12,
+                entries: 2,
+                words: 30,
+            },
+            DbEntryHistogramBucket {
+                key: 1,
+                entries: 1,
+                words: 5,
+            },
+        ];
+        let buckets = histogram(rows, 1, 12);
+        assert_eq!(buckets.len(), 12);
+        assert_eq!((buckets[0].entries, buckets[0].words), (1, 5));
+        assert_eq!((buckets[11].entries, buckets[11].words), (2, 30));
+        assert_eq!(buckets[5].entries, 0);
+    }
+}
@@ -0,0 +1,154 @@
+// a day counts towards the streak if its entry has at least the user's daily
+// word goal. writing earns freezes, which keep the streak alive over a day
+// without enough words instead of resetting it.
+
+use serde::Serialize;
+use time::Date;
+
+use crate::datetime::AppDate;
+
+/// goal-met days needed in a row to earn a freeze
+pub const FREEZE_EARNED_EVERY: i64 = 7;
+/// freezes that can be saved up
+pub const MAX_FREEZES: i64 = 2;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Streak {
+    goal: i64,
+    today: AppDate,
+    current: i64,
+    longest: i64,
+    /// saved up freezes, used for the next missed days
+    freezes: i64,
+    goal_met_days: Vec<AppDate>,
+    /// missed days on which a freeze was used
+    freeze_days: Vec<AppDate>,
+}
+
+/// `goal_met_days` must be in date order. days after `today` are ignored, and
+/// `today` only counts once the goal is met, since it isn't over yet.
+pub fn compute(goal: i64, goal_met_days: &[Date], today: Date) -> Streak {
+    let goal_met_days: Vec<Date> = goal_met_days
+        .iter()
+        .copied()
+        .filter(|day| *day <= today)
+        .collect();
+
+    let mut current = 0;
+    let mut longest = 0;
+    let mut freezes = 0;
+    // goal-met days towards the next freeze
+    let mut earning = 0;
+    let mut freeze_days = Vec::new();
+
+    let mut met = goal_met_days.iter().peekable();
+    let mut day = goal_met_days.first().copied();
+    while let Some(date) = day.filter(|date| *date <= today) {
+        if met.next_if(|met| **met == date).is_some() {
+            current += 1;
+            longest = longest.max(current);
+            earning += 1;
+            if earning == FREEZE_EARNED_EVERY {
+                earning = 0;
+                freezes = (freezes + 1).min(MAX_FREEZES);
+            }
+        } else if date == today {
+            // the goal can still be met today
+        } else if current > 0 && freezes > 0 {
+            freezes -= 1;
+            freeze_days.push(AppDate(date));
+        } else {
+            current = 0;
+            earning = 0;
+        }
+        day = date.next_day();
+    }
+
+    Streak {
+        goal,
+        today: AppDate(today),
+        current,
+        longest,
+        freezes,
+        goal_met_days: goal_met_days.into_iter().map(AppDate).collect(),
+        freeze_days,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::{Duration, macros::date};
+
+    fn days(first: Date, count: i64) -> Vec<Date> {
+        (0..count).map(|i| first + Duration::days(i)).collect()
+    }
+
+    #[test]
+    fn no_entries() {
+        let streak = compute(750, &[], date!(2026 - 10 - 18));
+        assert_eq!(streak.current, 0);
+        assert_eq!(streak.longest, 0);
+        assert!(streak.goal_met_days.is_empty());
+        assert_eq!(streak.today.to_string(), "2026-10-18");
+    }
+
+    #[test]
+    fn today_not_written_yet() {
+        let today = date!(2026 - 10 - 18);
+        let streak = compute(750, &days(date!(2026 - 10 - 15), 3), today);
+        assert_eq!((streak.current, streak.longest), (3, 3));
+
+        let streak = compute(750, &days(date!(2026 - 10 - 15), 4), today);
+        assert_eq!((streak.current, streak.longest), (4, 4));
+    }
+
+    #[test]
+    fn missed_day_without_freeze_resets() {
+        let mut met = days(date!(2026 - 10 - 01), 5);
+        met.extend(days(date!(2026 - 10 - 15), 3));
+        let streak = compute(750, &met, date!(2026 - 10 - 18));
+        assert_eq!((streak.current, streak.longest), (3, 5));
+        assert!(streak.freeze_days.is_empty());
+
+        let streak = compute(750, &days(date!(2026 - 10 - 01), 5), date!(2026 - 10 - 18));
+        assert_eq!((streak.current, streak.longest), (0, 5));
+    }
+
+    #[test]
+    fn freezes_bridge_missed_days() {
+        // 14 days earn 2 freezes, which cover the 2 missed days
+        let mut met = days(date!(2026 - 10 - 01), 14);
+        met.push(date!(2026 - 10 - 17));
+        let streak = compute(750, &met, date!(2026 - 10 - 17));
+        assert_eq!((streak.current, streak.longest), (15, 15));
+        assert_eq!(
+            streak.freeze_days,
+            vec![
+                AppDate(date!(2026 - 10 - 15)),
+                AppDate(date!(2026 - 10 - 16))
+            ]
+        );
+        assert_eq!(streak.freezes, 0);
+
+        // a third missed day breaks it
+        let mut met = days(date!(2026 - 10 - 01), 14);
+        met.push(date!(2026 - 10 - 18));
+        let streak = compute(750, &met, date!(2026 - 10 - 18));
+        assert_eq!((streak.current, streak.longest), (1, 14));
+    }
+
+    #[test]
+    fn freezes_are_capped() {
+        let streak = compute(750, &days(date!(2026 - 09 - 01), 30), date!(2026 - 09 - 30));
+        assert_eq!(streak.freezes, MAX_FREEZES);
+    }
+
+    #[test]
+    fn future_days_are_ignored() {
+        let met = vec![date!(2026 - 10 - 17), date!(2026 - 10 - 19)];
+        let streak = compute(750, &met, date!(2026 - 10 - 18));
+        assert_eq!(streak.current, 1);
+        assert_eq!(streak.goal_met_days, vec![AppDate(date!(2026 - 10 - 17))]);
+    }
+}
@@ -21,6 +21,9 @@ export type JSONContent = {
 ref: https://github.com/ueberdosis/tiptap/blob/71db1f26e8a998c1675f183d99eee4291f45e50f/packages/core/src/types.ts#L425
 */
 
+/// between paragraphs, headings, etc. in plain text
+const BLOCK_SEPARATOR: &str = "\n\n";
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TiptapMark {
     #[serde(rename = "type")]
@@ -43,29 +46,129 @@ pub struct TiptapJsonContent {
 }
 
 impl TiptapJsonContent {
+    /// the text of adjacent text nodes is counted together, so that e.g. a
+    /// partly bold word counts once
     pub fn count_words(&self) -> i64 {
-        let mut ans = 0;
-        // list of items to be processed
-        let mut queue = vec![self];
+        let mut text = String::new();
+        // list of items to be processed, in document order
+        let mut stack = vec![self];
 
-        while let Some(jc) = queue.pop() {
-            if let Some(text) = &jc.text {
-                ans += utils::count_words(text);
+        while let Some(jc) = stack.pop() {
+            match &jc.text {
+                Some(node_text) => text.push_str(node_text),
+                // blocks and hard breaks separate words
+                None => text.push('\n'),
             }
 
             // put up nested content for processing
             if let Some(content) = &jc.content {
-                queue.extend(content);
+                stack.extend(content.iter().rev());
             }
         }
 
-        ans
+        utils::count_words(&text)
+    }
+
+    /// text of the document without formatting. blocks are separated by a
+    /// blank line, list items are on their own line, prefixed with `-` or
+    /// their number, and code blocks keep their line breaks.
+    pub fn to_plain_text(&self) -> String {
+        self.block_text()
+    }
+
+    fn children(&self) -> &[TiptapJsonContent] {
+        self.content.as_deref().unwrap_or_default()
+    }
+
+    fn is_inline(&self) -> bool {
+        self.text.is_some() || self.type_.as_deref() == Some("hardBreak")
+    }
+
+    fn inline_text(&self) -> String {
+        let mut text = String::new();
+        for jc in self.children() {
+            match (&jc.text, jc.type_.as_deref()) {
+                (Some(t), _) => text.push_str(t),
+                (None, Some("hardBreak")) => text.push('\n'),
+                // e.g. inline nodes of extensions the server doesn't know
+                (None, _) => text.push_str(&jc.inline_text()),
+            }
+        }
+        text
+    }
+
+    /// empty blocks, like the empty paragraphs used for spacing, are skipped
+    fn blocks_text(&self, separator: &str) -> String {
+        self.children()
+            .iter()
+            .map(|jc| jc.block_text())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    fn list_text(&self, marker: impl Fn(i64) -> String) -> String {
+        self.children()
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let marker = marker(i as i64);
+                // continuation lines and nested lists line up with the text
+                let indent = " ".repeat(marker.chars().count());
+                prefix_lines(&item.block_text(), &marker, &indent)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // node types registered by the client editor
+    // ref: client/src/pages/writings/[date].tsx
+    fn block_text(&self) -> String {
+        match self.type_.as_deref() {
+            Some("doc") => self.blocks_text(BLOCK_SEPARATOR),
+            Some("paragraph" | "heading" | "codeBlock") => self.inline_text(),
+            Some("blockquote") => prefix_lines(&self.blocks_text(BLOCK_SEPARATOR), "> ", "> "),
+            Some("bulletList") => self.list_text(|_| "- ".to_string()),
+            Some("orderedList") => {
+                let start = self
+                    .attrs
+                    .as_ref()
+                    .and_then(|attrs| attrs.get("start"))
+                    .and_then(Value::as_i64)
+                    .unwrap_or(1);
+                self.list_text(|i| format!("{}. ", start + i))
+            }
+            Some("listItem") => self.blocks_text("\n"),
+            Some("horizontalRule") => "---".to_string(),
+            Some("hardBreak") => "\n".to_string(),
+            _ => match &self.text {
+                Some(text) => text.clone(),
+                None if self.children().iter().all(|jc| jc.is_inline()) => self.inline_text(),
+                None => self.blocks_text(BLOCK_SEPARATOR),
+            },
+        }
     }
 }
 
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     #[test]
     fn word_count() {
@@ -73,4 +176,187 @@ mod tests {
         let parsed = serde_json::from_str::<TiptapJsonContent>(json).unwrap();
         assert_eq!(parsed.count_words(), 460);
     }
+
+    #[test]
+    fn word_count_across_text_nodes() {
+        let doc = json!({
+            "type": "doc",
+            "content": [
+                { "type": "paragraph", "content": [
+                    text("partly "),
+                    { "type": "text", "text": "bo", "marks": [{ "type": "bold" }] },
+                    text("ld"),
+                    { "type": "hardBreak" },
+                    text("next"),
+                ] },
+                paragraph("line"),
+            ]
+        });
+        let parsed = serde_json::from_value::<TiptapJsonContent>(doc).unwrap();
+        assert_eq!(parsed.count_words(), 4);
+    }
+
+    fn plain_text(doc: Value) -> String {
+        serde_json::from_value::<TiptapJsonContent>(doc)
+            .unwrap()
+            .to_plain_text()
+    }
+
+    fn text(text: &str) -> Value {
+        json!({ "type": "text", "text": text })
+    }
+
+    fn paragraph(text: &str) -> Value {
+        json!({ "type": "paragraph", "content": [{ "type": "text", "text": text }] })
+    }
+
+    fn list_item(content: Vec<Value>) -> Value {
+        json!({ "type": "listItem", "content": content })
+    }
+
+    #[test]
+    fn plain_text_paragraphs_and_headings() {
+        let doc = json!({
+            "type": "doc",
+            "content": [
+                { "type": "heading", "attrs": { "level": 1 }, "content": [text("Title")] },
+                paragraph("first"),
+                // empty paragraphs are only spacing
+                { "type": "paragraph" },
+                paragraph("second"),
+            ]
+        });
+        assert_eq!(plain_text(doc), "Title\n\nfirst\n\nsecond");
+    }
+
+    #[test]
+    fn plain_text_hard_break() {
+        let doc = json!({
+            "type": "doc",
+            "content": [{
+                "type": "paragraph",
+                "content": [text("line one"), { "type": "hardBreak" }, text("line two")]
+            }]
+        });
+        assert_eq!(plain_text(doc), "line one\nline two");
+    }
+
+    #[test]
+    fn plain_text_ignores_marks() {
+        let doc = json!({
+            "type": "doc",
+            "content": [{
+                "type": "paragraph",
+                "content": [
+                    { "type": "text", "marks": [{ "type": "bold" }], "text": "bold " },
+                    { "type": "text", "marks": [{ "type": "italic" }], "text": "italic " },
+                    { "type": "text", "marks": [{ "type": "strike" }], "text": "strike " },
+                    { "type": "text", "marks": [{ "type": "underline" }], "text": "underline " },
+                    { "type": "text", "marks": [{ "type": "code" }], "text": "code " },
+                    {
+                        "type": "text",
+                        "marks": [{ "type": "link", "attrs": { "href": "https://example.com" } }],
+                        "text": "link"
+                    },
+                ]
+            }]
+        });
+        assert_eq!(plain_text(doc), "bold italic strike underline code link");
+    }
+
+    #[test]
+    fn plain_text_bullet_list() {
+        let doc = json!({
+            "type": "doc",
+            "content": [{
+                "type": "bulletList",
+                "content": [
+                    list_item(vec![paragraph("one")]),
+                    list_item(vec![
+                        paragraph("two"),
+                        json!({
+                            "type": "bulletList",
+                            "content": [list_item(vec![paragraph("nested")])]
+                        }),
+                    ]),
+                ]
+            }]
+        });
+        assert_eq!(plain_text(doc), "- one\n- two\n  - nested");
+    }
+
+    #[test]
+    fn plain_text_ordered_list() {
+        let doc = json!({
+            "type": "doc",
+            "content": [
+                {
+                    "type": "orderedList",
+                    "attrs": { "start": 9 },
+                    "content": [
+                        list_item(vec![paragraph("nine")]),
+                        list_item(vec![paragraph("ten"), paragraph("continued")]),
+                    ]
+                },
+                {
+                    "type": "orderedList",
+                    "content": [list_item(vec![paragraph("one")])]
+                },
+            ]
+        });
+        assert_eq!(plain_text(doc), "9. nine\n10. ten\n    continued\n\n1. one");
+    }
+
+    #[test]
+    fn plain_text_code_block() {
+        let doc = json!({
+            "type": "doc",
+            "content": [
+                paragraph("before"),
+                {
+                    "type": "codeBlock",
+                    "attrs": { "language": "rust" },
+                    "content": [text("fn main() {\n    println!(\"hi\");\n}")]
+                },
+                paragraph("after"),
+            ]
+        });
+        assert_eq!(
+            plain_text(doc),
+            "before\n\nfn main() {\n    println!(\"hi\");\n}\n\nafter"
+        );
+    }
+
+    #[test]
+    fn plain_text_blockquote() {
+        let doc = json!({
+            "type": "doc",
+            "content": [{
+                "type": "blockquote",
+                "content": [paragraph("quoted"), paragraph("twice")]
+            }]
+        });
+        assert_eq!(plain_text(doc), "> quoted\n>\n> twice");
+    }
+
+    #[test]
+    fn plain_text_horizontal_rule() {
+        let doc = json!({
+            "type": "doc",
+            "content": [paragraph("above"), { "type": "horizontalRule" }, paragraph("below")]
+        });
+        assert_eq!(plain_text(doc), "above\n\n---\n\nbelow");
+    }
+
+    #[test]
+    fn plain_text_unknown_nodes() {
+        let doc = json!({
+            "type": "doc",
+            "content": [
+                { "type": "callout", "content": [paragraph("block"), paragraph("children")] },
+                { "type": "fancyParagraph", "content": [text("inline")] },
+            ]
+        });
+        assert_eq!(plain_text(doc), "block\n\nchildren\n\ninline");
+    }
 }
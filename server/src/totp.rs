@@ -0,0 +1,160 @@
+// ref: https://datatracker.ietf.org/doc/html/rfc6238
+
+use rand_core::{OsRng, RngCore};
+use subtle::ConstantTimeEq;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const ISSUER: &str = "3pages";
+const DIGITS: usize = 6;
+const STEP: u64 = 30;
+/// steps before and after the current one which are still accepted, to allow
+/// for clock drift
+const SKEW: i64 = 1;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+// no 0/O or 1/I/L, so the codes can be typed from paper without confusion
+const RECOVERY_CODE_ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+fn totp(secret: &str, account_name: &str) -> Result<TOTP, totp_rs::TotpUrlError> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|_| totp_rs::TotpUrlError::Secret(secret.to_string()))?;
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        SKEW as u8,
+        STEP,
+        secret,
+        Some(ISSUER.to_string()),
+        account_name.to_string(),
+    )
+}
+
+/// base32 encoded, 160 bits as recommended by rfc 4226
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    OsRng.fill_bytes(&mut bytes);
+    Secret::Raw(bytes.to_vec()).to_encoded().to_string()
+}
+
+/// for authenticator apps, usually shown as a qr code
+/// ref: https://github.com/google/google-authenticator/wiki/Key-Uri-Format
+pub fn otpauth_uri(secret: &str, email: &str) -> Result<String, totp_rs::TotpUrlError> {
+    Ok(totp(secret, email)?.get_url())
+}
+
+/// returns the time step the code belongs to, if it is valid and the step is
+/// newer than `last_used_step`. callers must store the step, so that a code
+/// can't be replayed.
+pub fn verify(secret: &str, code: &str, last_used_step: Option<i64>, now: i64) -> Option<i64> {
+    let totp = totp(secret, "").ok()?;
+    let code = code.trim();
+    let current_step = now / STEP as i64;
+
+    (current_step - SKEW..=current_step + SKEW)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        // in constant time, so the response time doesn't tell how many digits
+        // of a guess are right
+        .find(|step| {
+            totp.generate((*step as u64) * STEP)
+                .as_bytes()
+                .ct_eq(code.as_bytes())
+                .into()
+        })
+}
+
+#[cfg(test)]
+pub fn generate(secret: &str, now: i64) -> String {
+    totp(secret, "").expect("valid secret").generate(now as u64)
+}
+
+/// one time codes to log in when the authenticator is lost
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code = (0..RECOVERY_CODE_LENGTH)
+                .map(|_| {
+                    let index = OsRng.next_u32() as usize % RECOVERY_CODE_ALPHABET.len();
+                    RECOVERY_CODE_ALPHABET[index] as char
+                })
+                .collect::<String>();
+            format!(
+                "{}-{}",
+                &code[..RECOVERY_CODE_LENGTH / 2],
+                &code[RECOVERY_CODE_LENGTH / 2..]
+            )
+        })
+        .collect()
+}
+
+/// users may type codes in lowercase, with or without the dash
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // rfc 6238 appendix B uses the ascii secret "12345678901234567890"
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn rfc6238_test_vectors() {
+        // the rfc uses 8 digits, these are the last 6 of them
+        assert_eq!(verify(RFC_SECRET, "287082", None, 59), Some(1));
+        assert_eq!(
+            verify(RFC_SECRET, "081804", None, 1111111109),
+            Some(37037036)
+        );
+        assert_eq!(
+            verify(RFC_SECRET, "050471", None, 1111111111),
+            Some(37037037)
+        );
+        assert_eq!(
+            verify(RFC_SECRET, "005924", None, 1234567890),
+            Some(41152263)
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_code() {
+        assert_eq!(verify(RFC_SECRET, "000000", None, 59), None);
+        assert_eq!(verify(RFC_SECRET, "", None, 59), None);
+    }
+
+    #[test]
+    fn accepts_previous_step() {
+        assert_eq!(verify(RFC_SECRET, "287082", None, 59 + 30), Some(1));
+        assert_eq!(verify(RFC_SECRET, "287082", None, 59 + 60), None);
+    }
+
+    #[test]
+    fn rejects_replayed_code() {
+        assert_eq!(verify(RFC_SECRET, "287082", Some(1), 59), None);
+        assert_eq!(verify(RFC_SECRET, "287082", Some(0), 59), Some(1));
+    }
+
+    #[test]
+    fn generated_secret_roundtrip() {
+        let secret = generate_secret();
+        let uri = otpauth_uri(&secret, "jack@example.com").unwrap();
+        assert!(uri.starts_with("otpauth://totp/3pages:jack%40example.com?"));
+        assert!(uri.contains(&format!("secret={}", secret)));
+    }
+
+    #[test]
+    fn recovery_codes() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        for code in &codes {
+            assert_eq!(code.len(), RECOVERY_CODE_LENGTH + 1);
+            assert_eq!(normalize_recovery_code(code).len(), RECOVERY_CODE_LENGTH);
+        }
+        assert_eq!(normalize_recovery_code(" abcde-fghij "), "ABCDEFGHIJ");
+    }
+}
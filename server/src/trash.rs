@@ -0,0 +1,25 @@
+use std::{sync::Arc, time::Duration};
+
+use log::{error, info};
+
+use crate::{db::Repository, env::Env};
+
+/// retention is in days, checking more often than hourly is pointless
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// deletes entries which have been in the trash for longer than the retention
+pub fn spawn_purge_task(repo: Arc<dyn Repository>) {
+    let retention = Env::get().entry_trash_retention;
+    tokio::spawn(async move {
+        // ref: https://docs.rs/tokio/1.46.1/tokio/time/fn.interval.html
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match repo.purge_entries_by_deleted_before(retention).await {
+                Ok(0) => {}
+                Ok(count) => info!("Purged {} entries from the trash", count),
+                Err(err) => error!("Failed to purge the trash: {}", err),
+            }
+        }
+    });
+}
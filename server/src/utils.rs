@@ -1,51 +1,38 @@
 use crate::env::Env;
-use argon2::{
-    Argon2, PasswordVerifier,
-    password_hash::{PasswordHasher, SaltString},
-};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, TokenData, Validation, decode, encode};
-use rand_core::OsRng;
+use rand_core::{OsRng, RngCore};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use time::OffsetDateTime;
+use time_tz::timezones;
+use unicode_segmentation::UnicodeSegmentation;
+use validator::ValidationError;
 
-pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
-    // ref: https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html#argon2id
-    let params = argon2::ParamsBuilder::new()
-        .m_cost(47104)
-        .t_cost(1)
-        .p_cost(1)
-        .build()
-        .expect("Expected valid argon2 parameters");
-    let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
-    let salt = SaltString::generate(&mut OsRng);
-
-    argon2
-        .hash_password(password.as_bytes(), &salt)
-        .map(|ph| ph.to_string())
-}
-
-pub fn verify_password(password: &str, password_hash: &str) -> bool {
-    argon2::PasswordHash::new(password_hash).is_ok_and(|parsed| {
-        Argon2::default()
-            .verify_password(password.as_bytes(), &parsed)
-            .is_ok()
-    })
-}
+/// access tokens can't be revoked before the session lookup in
+/// `middleware::authenticate`, so keep them short lived
+pub const ACCESS_TOKEN_TTL: time::Duration = time::Duration::minutes(15);
+pub const REFRESH_TOKEN_TTL: time::Duration = time::Duration::days(30);
+pub const PASSWORD_RESET_TOKEN_TTL: time::Duration = time::Duration::hours(1);
+pub const TOTP_CHALLENGE_TTL: time::Duration = time::Duration::minutes(5);
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     // match js server behavior
     #[serde(rename = "userId")]
     pub user_id: i64,
+    #[serde(rename = "sessionId")]
+    pub session_id: i64,
     iat: usize, // Optional. Issued at (as UTC timestamp)
     exp: usize, // Required (validate_exp defaults to true in validation). Expiration time (as UTC timestamp)
 }
 
-pub fn create_jwt(user_id: i64) -> Result<String, jsonwebtoken::errors::Error> {
+pub fn create_jwt(user_id: i64, session_id: i64) -> Result<String, jsonwebtoken::errors::Error> {
     let claims = Claims {
         user_id,
+        session_id,
         iat: OffsetDateTime::now_utc().unix_timestamp() as usize,
-        exp: (OffsetDateTime::now_utc() + time::Duration::days(7)).unix_timestamp() as usize,
+        exp: (OffsetDateTime::now_utc() + ACCESS_TOKEN_TTL).unix_timestamp() as usize,
     };
 
     encode(
@@ -64,23 +51,139 @@ pub fn decode_jwt(token: &str) -> Option<TokenData<Claims>> {
     .ok()
 }
 
-pub fn count_words(text: &str) -> i64 {
-    let mut word_count = 0;
-    let mut in_word = false;
-
-    for char in text.chars() {
-        let is_whitespace = char == '\r' || char == '\t' || char == ' ';
-        if !is_whitespace && !in_word {
-            // Starting a new word
-            word_count += 1;
-            in_word = true;
-        } else if is_whitespace {
-            // Ending a word
-            in_word = false;
-        }
+const TOTP_CHALLENGE_PURPOSE: &str = "totp";
+
+/// proves that the password step of the login succeeded. can't be used as an
+/// access token, since it has no `sessionId`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengeClaims {
+    #[serde(rename = "userId")]
+    pub user_id: i64,
+    purpose: String,
+    iat: usize,
+    exp: usize,
+}
+
+pub fn create_totp_challenge_jwt(user_id: i64) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = ChallengeClaims {
+        user_id,
+        purpose: TOTP_CHALLENGE_PURPOSE.to_string(),
+        iat: OffsetDateTime::now_utc().unix_timestamp() as usize,
+        exp: (OffsetDateTime::now_utc() + TOTP_CHALLENGE_TTL).unix_timestamp() as usize,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(Env::get().jwt_secret.as_ref()),
+    )
+}
+
+pub fn decode_totp_challenge_jwt(token: &str) -> Option<TokenData<ChallengeClaims>> {
+    decode::<ChallengeClaims>(
+        token,
+        &DecodingKey::from_secret(Env::get().jwt_secret.as_ref()),
+        &Validation::default(),
+    )
+    .ok()
+    .filter(|data| data.claims.purpose == TOTP_CHALLENGE_PURPOSE)
+}
+
+/// random url safe secret with 256 bits of entropy
+pub fn generate_token_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// token secrets have enough entropy that a fast hash is sufficient, unlike
+/// passwords which need argon2
+pub fn hash_token(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// refresh tokens are opaque to the client, but are of the form
+/// `<session id>.<secret>` so that the session can be looked up directly
+pub fn create_refresh_token(session_id: i64, secret: &str) -> String {
+    format!("{}.{}", session_id, secret)
+}
+
+pub fn parse_refresh_token(token: &str) -> Option<(i64, &str)> {
+    let (session_id, secret) = token.split_once('.')?;
+    let session_id = session_id.parse::<i64>().ok()?;
+    if secret.is_empty() {
+        return None;
     }
+    Some((session_id, secret))
+}
 
-    word_count
+/// strong etag of an entry revision
+pub fn entry_etag(revision: i64) -> String {
+    format!("\"{}\"", revision)
+}
+
+/// whether the `If-Match` header allows changing an entry at `revision`,
+/// `None` if the entry doesn't exist. weak etags never match.
+/// ref: https://www.rfc-editor.org/rfc/rfc9110#name-if-match
+pub fn if_match_allows(if_match: &str, revision: Option<i64>) -> bool {
+    let Some(revision) = revision else {
+        return false;
+    };
+    let if_match = if_match.trim();
+    if if_match == "*" {
+        return true;
+    }
+    let etag = entry_etag(revision);
+    if_match.split(',').any(|tag| tag.trim() == etag)
+}
+
+/// the text on a single line, cut at `max_chars` characters with an ellipsis
+pub fn preview(text: &str, max_chars: usize) -> String {
+    let mut words = text.split_whitespace();
+    let mut preview = words.next().unwrap_or_default().to_string();
+    for word in words {
+        preview.push(' ');
+        preview.push_str(word);
+    }
+    match preview.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", preview[..end].trim_end()),
+        None => preview,
+    }
+}
+
+/// han, hiragana and katakana are written without spaces, so every character
+/// counts as a word. hangul is written with spaces like latin scripts.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{309F}' // hiragana
+        | '\u{30A0}'..='\u{30FF}' // katakana
+        | '\u{31F0}'..='\u{31FF}' // katakana phonetic extensions
+        | '\u{3400}'..='\u{4DBF}' // cjk unified ideographs extension a
+        | '\u{4E00}'..='\u{9FFF}' // cjk unified ideographs
+        | '\u{F900}'..='\u{FAFF}' // cjk compatibility ideographs
+        | '\u{FF66}'..='\u{FF9D}' // halfwidth katakana
+        | '\u{20000}'..='\u{3134F}' // cjk unified ideographs extension b to g
+    )
+}
+
+/// words as in unicode text segmentation, so punctuation like em-dashes
+/// separates words and doesn't count by itself
+/// ref: https://www.unicode.org/reports/tr29/#Word_Boundaries
+pub fn count_words(text: &str) -> i64 {
+    text.unicode_words()
+        .map(|word| match word.chars().filter(|c| is_cjk(*c)).count() {
+            0 => 1,
+            cjk_chars => cjk_chars as i64,
+        })
+        .sum()
+}
+
+/// an IANA name like `Asia/Kolkata`
+pub fn validate_timezone(name: &str) -> Result<(), ValidationError> {
+    match timezones::get_by_name(name) {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new("timezone")),
+    }
 }
 
 pub mod trimmed_string {
@@ -95,24 +198,76 @@ pub mod trimmed_string {
     }
 }
 
+/// use together with `#[serde(default)]`
+pub mod optional_trimmed_string {
+    use serde::{self, Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = Option::<String>::deserialize(deserializer)?;
+        Ok(s.map(|s| s.trim().to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    /// to ensure that we can verify existing password hashes generated by js
-    /// server.
-    fn verify_existing_hash() {
-        let password = "jack@example.com";
-        let old_hash = "$argon2id$v=19$m=47104,t=2,p=1$Wej+XgzGzI6kDYK+8F3DPA$9aeemDzDmYWBr3aUBiUl4m3WvcFUOB1f1+aSfnUM5X4";
-        assert!(verify_password(password, old_hash));
+    fn refresh_token_roundtrip() {
+        let secret = generate_token_secret();
+        let token = create_refresh_token(42, &secret);
+        assert_eq!(parse_refresh_token(&token), Some((42, secret.as_str())));
+    }
+
+    #[test]
+    fn refresh_token_rejects_malformed() {
+        assert_eq!(parse_refresh_token(""), None);
+        assert_eq!(parse_refresh_token("42"), None);
+        assert_eq!(parse_refresh_token("42."), None);
+        assert_eq!(parse_refresh_token("abc.secret"), None);
+    }
+
+    #[test]
+    fn preview_text() {
+        assert_eq!(preview("  one\ntwo\n\nthree  ", 20), "one two three");
+        assert_eq!(preview("one two three", 8), "one two…");
+        assert_eq!(preview("one two three", 13), "one two three");
+        assert_eq!(preview("日本語のテキスト", 3), "日本語…");
+        assert_eq!(preview("", 3), "");
+    }
+
+    #[test]
+    fn word_count() {
+        assert_eq!(count_words(""), 0);
+        assert_eq!(count_words("  one two\tthree\r\nfour\n"), 4);
+        assert_eq!(count_words("non\u{A0}breaking"), 2);
+        assert_eq!(count_words("em\u{2014}dash and en \u{2013} dash"), 5);
+        // punctuation alone is not a word
+        assert_eq!(count_words("yes - no ... ! \u{A9}"), 2);
+        assert_eq!(count_words("don't e.g. 3.14 fsf.org"), 4);
+    }
+
+    #[test]
+    fn word_count_cjk() {
+        assert_eq!(count_words("日本語の文章です。"), 8);
+        assert_eq!(count_words("ラーメンを食べた"), 8);
+        assert_eq!(count_words("中文 text"), 3);
+        assert_eq!(count_words("한국어 문장입니다"), 2);
     }
 
     #[test]
-    /// to ensure that we can verify newly created password hashes
-    fn verify_new_hash() {
-        let password = "jack@example.com";
-        let new_hash = hash_password(password).expect("Failed to hash password");
-        assert!(verify_password(password, &new_hash));
+    fn if_match() {
+        assert!(if_match_allows("\"3\"", Some(3)));
+        assert!(if_match_allows("\"1\", \"3\"", Some(3)));
+        assert!(if_match_allows("*", Some(3)));
+        assert!(!if_match_allows("\"2\"", Some(3)));
+        assert!(!if_match_allows("W/\"3\"", Some(3)));
+        assert!(!if_match_allows("3", Some(3)));
+        // nothing to match against
+        assert!(!if_match_allows("*", None));
+        assert!(!if_match_allows("\"3\"", None));
     }
 }